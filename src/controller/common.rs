@@ -3,6 +3,13 @@ use log::info;
 use serde_json::{json, Map, Value};
 use std::collections::BTreeMap;
 
+use crate::downscaler::resource::{
+    argocd::{original_sync_policy, sync_policy_patch, ORIGINAL_SYNC_POLICY_ANNOTATION},
+    daemonset::{node_selector_patch, original_node_selector, ORIGINAL_NODE_SELECTOR_ANNOTATION},
+    hpa::{hpa_replicas_patch, original_max_replicas, ORIGINAL_MAX_REPLICAS_ANNOTATION},
+    keda::PAUSED_REPLICAS_ANNOTATION,
+    vpa::{original_update_mode, update_mode_patch, ORIGINAL_UPDATE_MODE_ANNOTATION},
+};
 use crate::error::Error;
 use crate::{downscaler::Resources, parser::dynamic_resource_type};
 
@@ -24,7 +31,11 @@ impl UpscaleMachinery {
                 .get("kustomize.toolkit.fluxcd.io/reconcile")
                 .is_some();
             let spec = match self.resource_type {
-                Resources::Deployment | Resources::Namespace | Resources::StatefulSet => {
+                Resources::Deployment
+                | Resources::Namespace
+                | Resources::StatefulSet
+                | Resources::Rollout
+                | Resources::Scalable(_) => {
                     let replicas = self
                         .get_replicas(self.replicas, self.annotations.to_owned())
                         .await;
@@ -32,42 +43,103 @@ impl UpscaleMachinery {
                         "scaling up {} in namespace {} to {}",
                         self.name, self.namespace, replicas
                     );
-                    json!({ "replicas": replicas })
+                    Some(json!({ "replicas": replicas }))
                 }
                 Resources::Hpa => {
                     let replicas = self
                         .get_replicas(self.replicas, self.annotations.to_owned())
                         .await;
-                    json!({ "minReplicas": replicas }) // minReplicas should >=1
+                    Some(hpa_replicas_patch(
+                        replicas,
+                        original_max_replicas(self.annotations.as_ref()),
+                    ))
                 }
 
-                Resources::CronJob => {
+                Resources::CronJob
+                | Resources::Job
+                | Resources::HelmRelease
+                | Resources::Kustomization => {
                     info!(
-                        "Setting CronJob {} in namespace {} to Active",
-                        self.name, self.namespace,
+                        "Setting {} {} in namespace {} to Active",
+                        self.resource_type, self.name, self.namespace,
                     );
-                    json!(
+                    Some(json!(
                          {
                             "suspend": false
                         }
-                    )
+                    ))
+                }
+                Resources::DaemonSet => {
+                    info!(
+                        "Restoring the node selector of DaemonSet {} in namespace {}",
+                        self.name, self.namespace,
+                    );
+                    let original = original_node_selector(self.annotations.as_ref());
+                    Some(node_selector_patch(&original.unwrap_or_default(), false))
+                }
+                Resources::Application => {
+                    info!(
+                        "Restoring the automated sync of Application {} in namespace {}",
+                        self.name, self.namespace,
+                    );
+                    original_sync_policy(self.annotations.as_ref())
+                        .map(|automated| sync_policy_patch(Some(automated)))
+                }
+                Resources::Vpa => {
+                    info!(
+                        "Restoring the update mode of Vpa {} in namespace {}",
+                        self.name, self.namespace,
+                    );
+                    original_update_mode(self.annotations.as_ref())
+                        .map(|update_mode| update_mode_patch(Some(update_mode)))
+                }
+                Resources::ScaledObject => {
+                    info!(
+                        "Resuming ScaledObject {} in namespace {}",
+                        self.name, self.namespace,
+                    );
+                    None
                 }
             };
             let mut patch = Map::new();
-            patch.insert("spec".to_string(), spec);
+            if let Some(spec) = spec {
+                patch.insert("spec".to_string(), spec);
+            }
+            let mut annotations = Map::new();
             // If "flux" annotation is disabled, remove it
             if is_flux_disabled {
-                let annotations: Value = json!({
-                    "annotations": {
-                        "kustomize.toolkit.fluxcd.io/reconcile": null,
-                    }
-                });
-
-                patch.insert("metadata".to_string(), annotations);
+                annotations.insert(
+                    "kustomize.toolkit.fluxcd.io/reconcile".to_string(),
+                    Value::Null,
+                );
+            }
+            // keda resumes scaling once the paused replicas are removed
+            if self.resource_type == Resources::ScaledObject {
+                annotations.insert(PAUSED_REPLICAS_ANNOTATION.to_string(), Value::Null);
+            }
+            // the automated sync is restored from the saved policy
+            if self.resource_type == Resources::Application {
+                annotations.insert(ORIGINAL_SYNC_POLICY_ANNOTATION.to_string(), Value::Null);
+            }
+            if self.resource_type == Resources::Vpa {
+                annotations.insert(ORIGINAL_UPDATE_MODE_ANNOTATION.to_string(), Value::Null);
+            }
+            // maxReplicas is restored from the saved annotation
+            if self.resource_type == Resources::Hpa {
+                annotations.insert(ORIGINAL_MAX_REPLICAS_ANNOTATION.to_string(), Value::Null);
+            }
+            if self.resource_type == Resources::DaemonSet {
+                annotations.insert(ORIGINAL_NODE_SELECTOR_ANNOTATION.to_string(), Value::Null);
+            }
+            if !annotations.is_empty() {
+                patch.insert(
+                    "metadata".to_string(),
+                    json!({ "annotations": annotations }),
+                );
             }
 
             let patch_object = Value::Object(patch);
-            let rs = dynamic_resource_type(c, &self.namespace, self.resource_type);
+            let rs = dynamic_resource_type(c, &self.namespace, &self.resource_type);
             match rs {
                 Some(rs) => rs.patch_resource(&self.name, &patch_object).await,
                 None => Ok(()),
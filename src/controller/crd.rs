@@ -1,8 +1,18 @@
-use kube::CustomResource;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::{Client, CustomResource, ResourceExt};
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{Schema, SchemaObject};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
-#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, JsonSchema)]
+use crate::clock::Clock;
+use crate::downscaler::{JmesPath, Rule, Selection};
+use crate::error::Error;
+use crate::schedule::Schedule;
+use crate::time_check::Margins;
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, JsonSchema)]
 #[kube(
     group = "kubesaver.com",
     version = "v1",
@@ -15,10 +25,404 @@ pub struct UpscalerSpec {
     pub scale: Vec<Resource>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, JsonSchema)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, JsonSchema)]
 pub struct Resource {
     pub resource: Vec<String>,
+    /// optional with a selector or namespaceSelector
+    #[serde(default)]
     pub jmespath: String,
+    /// label selector sent with the list calls, matchLabels and matchExpressions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Value>")]
+    pub selector: Option<LabelSelector>,
+    /// labels of the namespaces of the resources
+    #[serde(
+        default,
+        rename = "namespaceSelector",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(with = "Option<Value>")]
+    pub namespace_selector: Option<LabelSelector>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub replicas: Option<i32>,
+    /// `<DAYS> HH:MM-HH:MM TZ`, resources are upscaled only during the uptime window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<String>,
+    /// cron expression, resources are upscaled only when the most recent transition is an upscale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upschedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downschedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl Resource {
+    /// Returns true if the resources can be upscaled at the time of the clock, resources without
+    /// schedule are always upscaled
+    pub fn is_uptime(&self, clock: &dyn Clock) -> Result<bool, Error> {
+        if let Some(uptime) = &self.uptime {
+            if self.upschedule.is_some() || self.downschedule.is_some() {
+                return Err(Error::UserInputError(
+                    "Upscaler must define either uptime or upschedule/downschedule, not both"
+                        .to_owned(),
+                ));
+            }
+            return Schedule::windows(std::slice::from_ref(uptime), Margins::default())?
+                .is_uptime(clock.now());
+        }
+        match (&self.upschedule, &self.downschedule, &self.timezone) {
+            (None, None, _) => Ok(true),
+            (Some(upschedule), Some(downschedule), Some(timezone)) => {
+                Schedule::cron(upschedule, downschedule, timezone, Margins::default())?
+                    .is_uptime(clock.now())
+            }
+            _ => Err(Error::UserInputError(
+                "upschedule, downschedule and timezone must be defined together in Upscaler"
+                    .to_owned(),
+            )),
+        }
+    }
+
+    /// Resources to upscale, the jmespath is compiled and the namespaces of the namespace selector
+    /// are listed once for all the resources
+    pub async fn selection(&self, client: Client, owner: &str) -> Result<Selection, Error> {
+        let expression = match self.jmespath.as_str() {
+            "" if self.selector.is_none() && self.namespace_selector.is_none() => {
+                return Err(Error::UserInputError(format!(
+                    "{} must define jmespath, selector or namespaceSelector",
+                    owner
+                )));
+            }
+            "" => None,
+            jmespath => Some(JmesPath::compile(owner, jmespath)?),
+        };
+        Selection::new(
+            client,
+            expression.as_ref(),
+            self.selector.as_ref(),
+            self.namespace_selector.as_ref(),
+        )
+        .await
+    }
+
+    pub fn is_scheduled(&self) -> bool {
+        self.uptime.is_some() || self.upschedule.is_some() || self.downschedule.is_some()
+    }
+}
+
+/// Downscale rule managed as a cluster resource, the spec has the same fields as a rule of rules.yaml
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, JsonSchema)]
+#[kube(
+    group = "kubesaver.com",
+    version = "v1",
+    kind = "DownscaleRule",
+    plural = "downscalerules",
+    status = "DownscaleRuleStatus"
+)]
+pub struct DownscaleRuleSpec {
+    /// defaults to the name of the DownscaleRule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// `<DAYS> HH:MM-HH:MM TZ`, or timezone and a list of windows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "preserve_unknown_fields")]
+    pub uptime: Option<Value>,
+    /// cron expression, alternative to uptime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upschedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downschedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// optional with a selector or namespaceSelector
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jmespath: Option<String>,
+    pub resource: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<i32>,
+    // the other fields of a rule, validated when the rule is evaluated
+    #[serde(flatten)]
+    pub rule: Map<String, Value>,
+}
+
+// string or object, validated when the rule is evaluated
+fn preserve_unknown_fields(_: &mut SchemaGenerator) -> Schema {
+    let mut schema = SchemaObject::default();
+    schema.extensions.insert(
+        "x-kubernetes-preserve-unknown-fields".to_owned(),
+        Value::Bool(true),
+    );
+    Schema::Object(schema)
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DownscaleRuleStatus {
+    /// RFC 3339 time of the last evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_evaluation: Option<String>,
+    /// `up` or `down` at the last evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_transition: Option<String>,
+    /// resources scaled by the last transition, as `Kind namespace/name`
+    #[serde(default)]
+    pub matched_resources: Vec<String>,
+    /// why the rule could not be evaluated
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl DownscaleRule {
+    /// Parses and validates the rule of the spec, the id defaults to the name of the resource
+    pub(crate) fn rule(&self) -> Result<Rule, Error> {
+        let Ok(Value::Object(mut fields)) = serde_json::to_value(&self.spec) else {
+            unreachable!("the spec serializes to an object")
+        };
+        fields
+            .entry("id")
+            .or_insert_with(|| Value::String(self.name_any()));
+        let mut rule = parse_rule("DownscaleRule", &self.name_any(), fields)?;
+        rule.expression = rule.parse_selection()?;
+        Ok(rule)
+    }
+}
+
+/// Schedule of the workloads in its namespace, applied with the precedence of the rules accepting
+/// tenant schedules
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[kube(
+    group = "kubesaver.com",
+    version = "v1",
+    kind = "TenantSchedule",
+    plural = "tenantschedules",
+    namespaced,
+    status = "TenantScheduleStatus",
+    schema = "disabled"
+)]
+pub struct TenantScheduleSpec {
+    #[serde(flatten)]
+    pub schedule: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct TenantScheduleStatus {
+    /// RFC 3339 time of the last evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_evaluation: Option<String>,
+    /// `up` or `down` of the schedule itself at the last evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_transition: Option<String>,
+    /// ids of the rules applying the schedule to the namespace
+    #[serde(default)]
+    pub applied_rules: Vec<String>,
+    /// uptime of the schedule outside the tenant_max_uptime of the rules, the schedule still applies
+    #[serde(default)]
+    pub clamped: Option<String>,
+    /// why the schedule could not be evaluated
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+// the resources and the precedence are set by the rules of the platform
+const TENANT_SCHEDULE_FIELDS: [&str; 9] = [
+    "uptime",
+    "upschedule",
+    "downschedule",
+    "timezone",
+    "prewarm",
+    "grace",
+    "holidays",
+    "ignore_holidays",
+    "exceptions",
+];
+
+impl TenantSchedule {
+    /// Parses and validates the schedule of the spec as a rule with the id `<namespace>/<name>`
+    pub(crate) fn rule(&self) -> Result<Rule, Error> {
+        let id = format!(
+            "{}/{}",
+            self.namespace().unwrap_or_default(),
+            self.name_any()
+        );
+        if let Some(field) = self
+            .spec
+            .schedule
+            .keys()
+            .find(|k| !TENANT_SCHEDULE_FIELDS.contains(&k.as_str()))
+        {
+            return Err(Error::UserInputError(format!(
+                "Invalid TenantSchedule {}: {} can't be set, expected one of {}",
+                id,
+                field,
+                TENANT_SCHEDULE_FIELDS.join(", ")
+            )));
+        }
+        let mut fields = self.spec.schedule.clone();
+        fields.insert("id".to_owned(), Value::String(id.clone()));
+        fields.insert("jmespath".to_owned(), Value::String(String::new()));
+        fields.insert("resource".to_owned(), Value::Array(vec![]));
+        parse_rule("TenantSchedule", &id, fields)
+    }
+}
+
+fn parse_rule(kind: &str, name: &str, fields: Map<String, Value>) -> Result<Rule, Error> {
+    let mut rule: Rule = serde_json::from_value(Value::Object(fields))
+        .map_err(|e| Error::UserInputError(format!("Invalid {} {}: {}", kind, name, e)))?;
+    rule.schedule = rule.parse_schedule()?;
+    rule.tenant_bound = rule.parse_tenant_bound()?;
+    Ok(rule)
+}
+
+#[test]
+fn validate_downscale_rule() {
+    let downscale_rule = |spec: Value| -> DownscaleRule {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "kubesaver.com/v1",
+            "kind": "DownscaleRule",
+            "metadata": { "name": "team-a" },
+            "spec": spec
+        }))
+        .unwrap()
+    };
+    let rule = downscale_rule(serde_json::json!({
+        "uptime": "Mon-Fri 07:00-19:00 Australia/Sydney",
+        "jmespath": "metadata.labels.team == 'a'",
+        "resource": ["Deployment"],
+        "replicas": 0
+    }))
+    .rule()
+    .unwrap();
+    assert_eq!(rule.id, "team-a");
+    assert_eq!(rule.replicas, Some(0));
+    assert!(rule.schedule.timezone().is_some());
+    assert_eq!(
+        downscale_rule(serde_json::json!({
+            "id": "team-a-nights",
+            "uptime": "Mon-Fri 07:00-19:00 Blah/Blah",
+            "jmespath": "metadata.labels.team == 'a'",
+            "resource": ["Deployment"]
+        }))
+        .rule()
+        .unwrap_err()
+        .to_string(),
+        "Invalid User Input: rule id team-a-nights: uptime Mon-Fri 07:00-19:00 Blah/Blah: Invalid timezone Blah/Blah, expected an Olson timezone for example Australia/Sydney"
+    );
+    // the resources are required by the schema
+    assert!(serde_json::from_value::<DownscaleRuleSpec>(
+        serde_json::json!({ "jmespath": "metadata.labels.team == 'a'" })
+    )
+    .unwrap_err()
+    .to_string()
+    .starts_with("missing field `resource`"));
+    // the other fields are validated when the rule is evaluated
+    assert!(downscale_rule(serde_json::json!({
+        "uptime": "Mon-Fri 07:00-19:00 Australia/Sydney",
+        "jmespath": "metadata.labels.team == 'a'",
+        "resource": ["Deployment"],
+        "active_jobs": "pause"
+    }))
+    .rule()
+    .unwrap_err()
+    .to_string()
+    .starts_with("Invalid User Input: Invalid DownscaleRule team-a: unknown variant `pause`"));
+    // resources are selected with a jmespath or a label selector
+    assert_eq!(
+        downscale_rule(serde_json::json!({
+            "uptime": "Mon-Fri 07:00-19:00 Australia/Sydney",
+            "resource": ["Deployment"]
+        }))
+        .rule()
+        .unwrap_err()
+        .to_string(),
+        "Invalid User Input: rule id team-a must define jmespath, selector or namespaceSelector"
+    );
+}
+
+#[test]
+fn downscale_rule_schema() {
+    use kube::CustomResourceExt;
+    let crd = serde_json::to_value(DownscaleRule::crd()).unwrap();
+    let spec = &crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"];
+    assert_eq!(spec["x-kubernetes-preserve-unknown-fields"], true);
+    assert_eq!(spec["required"], serde_json::json!(["resource"]));
+    assert_eq!(spec["properties"]["id"]["type"], "string");
+    assert_eq!(spec["properties"]["jmespath"]["type"], "string");
+    assert_eq!(spec["properties"]["resource"]["items"]["type"], "string");
+    assert_eq!(spec["properties"]["replicas"]["format"], "int32");
+    assert_eq!(
+        spec["properties"]["uptime"]["x-kubernetes-preserve-unknown-fields"],
+        true
+    );
+}
+
+#[test]
+fn validate_tenant_schedule() {
+    let tenant_schedule = |spec: Value| -> TenantSchedule {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "kubesaver.com/v1",
+            "kind": "TenantSchedule",
+            "metadata": { "name": "late-nights", "namespace": "team-a" },
+            "spec": spec
+        }))
+        .unwrap()
+    };
+    let rule = tenant_schedule(serde_json::json!({
+        "uptime": "Mon-Fri 07:00-23:00 Australia/Sydney",
+        "ignore_holidays": true
+    }))
+    .rule()
+    .unwrap();
+    assert_eq!(rule.id, "team-a/late-nights");
+    assert_eq!(rule.ignore_holidays, Some(true));
+    assert!(rule.resource.is_empty());
+    // tenants can't select resources outside their namespace
+    assert_eq!(
+        tenant_schedule(serde_json::json!({
+            "uptime": "Mon-Fri 07:00-23:00 Australia/Sydney",
+            "jmespath": "metadata.namespace == 'team-b'"
+        }))
+        .rule()
+        .unwrap_err()
+        .to_string(),
+        "Invalid User Input: Invalid TenantSchedule team-a/late-nights: jmespath can't be set, expected one of uptime, upschedule, downschedule, timezone, prewarm, grace, holidays, ignore_holidays, exceptions"
+    );
+    assert!(tenant_schedule(serde_json::json!({}))
+        .rule()
+        .unwrap_err()
+        .to_string()
+        .ends_with("must define either uptime or upschedule/downschedule"));
+}
+
+#[test]
+fn validate_upscaler_schedule() {
+    use crate::clock::{parse_rfc3339, FixedClock};
+    let resource = |schedule: Value| -> Resource {
+        let mut spec =
+            serde_json::json!({ "resource": ["Deployment"], "jmespath": "metadata.name == 'web'" });
+        spec.as_object_mut()
+            .unwrap()
+            .extend(schedule.as_object().unwrap().clone());
+        serde_json::from_value(spec).unwrap()
+    };
+    // Friday 18:59 and 19:01 in Sydney
+    let before = FixedClock(parse_rfc3339("2024-09-06T18:59:00+10:00").unwrap());
+    let after = FixedClock(parse_rfc3339("2024-09-06T19:01:00+10:00").unwrap());
+    let uptime = resource(serde_json::json!({ "uptime": "Mon-Fri 07:00-19:00 Australia/Sydney" }));
+    assert!(uptime.is_uptime(&before).unwrap());
+    assert!(!uptime.is_uptime(&after).unwrap());
+    let cron = resource(serde_json::json!({
+        "upschedule": "0 7 * * Mon-Fri",
+        "downschedule": "0 19 * * Mon-Fri",
+        "timezone": "Australia/Sydney"
+    }));
+    assert!(cron.is_uptime(&before).unwrap());
+    assert!(!cron.is_uptime(&after).unwrap());
+    // resources without schedule are always upscaled
+    assert!(resource(serde_json::json!({})).is_uptime(&after).unwrap());
 }
@@ -3,4 +3,4 @@ pub mod crd;
 pub mod finalizer;
 pub mod upscaler;
 pub mod watcher;
-pub use crd::Upscaler;
+pub use crd::{DownscaleRule, TenantSchedule, Upscaler};
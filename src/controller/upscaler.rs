@@ -1,10 +1,19 @@
 use crate::controller::common::UpscaleMachinery;
-use crate::downscaler::{JMSExpression, ResourceExtension, Resources};
+use crate::downscaler::resource::argocd::Application;
+use crate::downscaler::resource::common::list_or_empty;
+use crate::downscaler::resource::flux::{HelmRelease, Kustomization};
+use crate::downscaler::resource::keda::{KedaTargets, ScaledObject};
+use crate::downscaler::resource::rollout::Rollout;
+use crate::downscaler::resource::scale::discover_scalable;
+use crate::downscaler::resource::vpa::VerticalPodAutoscaler;
+use crate::downscaler::{ResourceExtension, Resources, Selection};
 use crate::error::Error;
-use k8s_openapi::api::autoscaling::v1::HorizontalPodAutoscaler;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use k8s_openapi::api::{
-    apps::v1::Deployment, apps::v1::StatefulSet, batch::v1::CronJob, core::v1::Namespace,
+    apps::v1::DaemonSet, apps::v1::Deployment, apps::v1::StatefulSet, batch::v1::CronJob,
+    batch::v1::Job, core::v1::Namespace,
 };
+use kube::api::{ApiResource, DynamicObject};
 use kube::{Api, Client};
 use log::debug;
 
@@ -12,24 +21,34 @@ use log::debug;
 pub async fn upscale_deploy(
     client: Client,
     replicas: Option<i32>,
-    expression: &str,
+    selection: &Selection,
 ) -> Result<(), Error> {
     let api: Api<Deployment> = Api::all(client.clone());
-    let list = api.list(&Default::default()).await?;
+    let list = api.list(&selection.list_params()).await?;
+    let so_api: Api<ScaledObject> = Api::all(client.clone());
+    let keda = KedaTargets::deployments(&so_api).await?;
     // parses the tag map object
     for item in &list.items {
         debug!("parsing deployment resource {:?}", item.metadata.name);
         // for the list of all deployment, check if the tag values matches with the specific deployment
         // For example: metadata.labels.app = nginx is matching with the deployment manifest
         // Invoke the trait JMSExpression default parse method. Deployment implements trait JMSExpression
-        let result = item.parse(expression).await?;
+        let result = selection.matches(item).await?;
         if result {
+            let namespace = item.metadata.namespace.as_ref().unwrap().to_string();
+            // deployments scaled by keda are resumed through their scaled object
+            let (name, annotations, resource_type) = keda.resolve(
+                &namespace,
+                item.metadata.name.as_ref().unwrap().to_string(),
+                item.metadata.annotations.to_owned(),
+                Resources::Deployment,
+            );
             let u = UpscaleMachinery {
                 replicas,
-                name: item.metadata.name.as_ref().unwrap().to_string(),
-                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
-                annotations: item.metadata.annotations.to_owned(),
-                resource_type: Resources::Deployment,
+                name,
+                namespace,
+                annotations,
+                resource_type,
             };
             u.upscale_machinery(client.clone()).await?
         }
@@ -42,17 +61,17 @@ pub async fn upscale_deploy(
 pub async fn upscale_statefulset(
     client: Client,
     replicas: Option<i32>,
-    expression: &str,
+    selection: &Selection,
 ) -> Result<(), Error> {
     let api: Api<StatefulSet> = Api::all(client.clone());
-    let list = api.list(&Default::default()).await?;
+    let list = api.list(&selection.list_params()).await?;
 
     for item in &list.items {
         debug!("parsing statefulset resource {:?}", item.metadata.name);
         // for the list of all statefulset, check if the tag values matches with the specific statefulset
         // For example: metadata.labels.app = nginx is matching with the statefulset manifest
         // Invoke the trait JMSExpression default parse method. Statefulset implements trait JMSExpression
-        let result = item.parse(expression).await?;
+        let result = selection.matches(item).await?;
         if result {
             let u = UpscaleMachinery {
                 replicas,
@@ -68,16 +87,226 @@ pub async fn upscale_statefulset(
     Ok(())
 }
 
+/// Upscale the Argo Rollout Resource when CustomResource Upscaler is applied to cluster
+pub async fn upscale_rollout(
+    client: Client,
+    replicas: Option<i32>,
+    selection: &Selection,
+) -> Result<(), Error> {
+    let api: Api<Rollout> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "Argo Rollouts").await?;
+    for item in &list.items {
+        debug!("parsing rollout resource {:?}", item.metadata.name);
+        // for the list of all rollout, check if the tag values matches with the specific rollout
+        // For example: metadata.labels.app = nginx is matching with the rollout manifest
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Rollout,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Upscale any kind exposing the /scale subresource when CustomResource Upscaler is applied to cluster
+pub async fn upscale_scalable(
+    client: Client,
+    resource: &ApiResource,
+    replicas: Option<i32>,
+    selection: &Selection,
+) -> Result<(), Error> {
+    let ar = discover_scalable(&client, resource).await?;
+    let api: Api<DynamicObject> = Api::all_with(client.clone(), &ar);
+    let list = api.list(&selection.list_params()).await?;
+    for item in &list.items {
+        debug!("parsing {} resource {:?}", ar.kind, item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Scalable(ar.clone()),
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Set Job Suspend status to False when CustomResource Upscaler is applied to cluster
+pub async fn resume_job(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<Job> = Api::all(client.clone());
+    let list = api.list(&selection.list_params()).await?;
+    for item in &list.items {
+        debug!("parsing job resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Job,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove the no node selector of the DaemonSet when CustomResource Upscaler is applied to cluster
+pub async fn enable_daemonset(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<DaemonSet> = Api::all(client.clone());
+    let list = api.list(&selection.list_params()).await?;
+    for item in &list.items {
+        debug!("parsing daemonset resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::DaemonSet,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Resume the Flux HelmRelease Resource when CustomResource Upscaler is applied to cluster
+pub async fn resume_helmrelease(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<HelmRelease> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "flux objects").await?;
+    for item in &list.items {
+        debug!("parsing helmrelease resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::HelmRelease,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Resume the Flux Kustomization Resource when CustomResource Upscaler is applied to cluster
+pub async fn resume_kustomization(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<Kustomization> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "flux objects").await?;
+    for item in &list.items {
+        debug!("parsing kustomization resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Kustomization,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Restore the update mode of the VerticalPodAutoscaler when CustomResource Upscaler is applied to cluster
+pub async fn resume_vpa(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<VerticalPodAutoscaler> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "vertical pod autoscalers").await?;
+    for item in &list.items {
+        debug!("parsing vpa resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Vpa,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Restore the automated sync of the ArgoCD Application when CustomResource Upscaler is applied to cluster
+pub async fn resume_application(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<Application> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "ArgoCD applications").await?;
+    for item in &list.items {
+        debug!("parsing application resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Application,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
+/// Resume the KEDA ScaledObject Resource when CustomResource Upscaler is applied to cluster
+pub async fn resume_scaled_object(client: Client, selection: &Selection) -> Result<(), Error> {
+    let api: Api<ScaledObject> = Api::all(client.clone());
+    let list = list_or_empty(&api, &selection.list_params(), "KEDA scaled objects").await?;
+    for item in &list.items {
+        debug!("parsing scaled object resource {:?}", item.metadata.name);
+        let result = selection.matches(item).await?;
+        if result {
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::ScaledObject,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+    }
+
+    Ok(())
+}
+
 /// Set CronJob Suspend status to False when CustomResource Upscaler is applied to cluster
-pub async fn enable_cronjob(client: Client, expression: &str) -> Result<(), Error> {
+pub async fn enable_cronjob(client: Client, selection: &Selection) -> Result<(), Error> {
     let api: Api<CronJob> = Api::all(client.clone());
-    let list = api.list(&Default::default()).await?;
+    let list = api.list(&selection.list_params()).await?;
     for item in &list.items {
         debug!("parsing cronjob resource {:?}", item.metadata.name);
         // for the list of all cronjob, check if the tag values matches with the specific cronjob
         // For example: metadata.labels.app = nginx is matching with the cronjob manifest
         // Invoke the trait JMSExpression default parse method. Statefulset implements trait JMSExpression
-        let result = item.parse(expression).await?;
+        let result = selection.matches(item).await?;
         if result {
             let u = UpscaleMachinery {
                 replicas: None,
@@ -97,16 +326,16 @@ pub async fn enable_cronjob(client: Client, expression: &str) -> Result<(), Erro
 pub async fn upscale_hpa(
     client: Client,
     replicas: Option<i32>,
-    expression: &str,
+    selection: &Selection,
 ) -> Result<(), Error> {
     let api: Api<HorizontalPodAutoscaler> = Api::all(client.clone());
-    let list = api.list(&Default::default()).await?;
+    let list = api.list(&selection.list_params()).await?;
     for item in &list.items {
         debug!("parsing hpa resource {:?}", item.metadata.name);
         // for the list of all Hpa, check if the tag values matches with the specific cronjob
         // For example: metadata.labels.app = nginx is matching with the cronjob manifest
         // Invoke the trait JMSExpression default parse method. Statefulset implements trait JMSExpression
-        let result = item.parse(expression).await?;
+        let result = selection.matches(item).await?;
         if result {
             let u = UpscaleMachinery {
                 replicas,
@@ -126,15 +355,15 @@ pub async fn upscale_hpa(
 pub async fn upscale_ns(
     client: Client,
     replicas: Option<i32>,
-    expression: &str,
+    selection: &Selection,
 ) -> Result<(), Error> {
     let api: Api<Namespace> = Api::all(client.clone());
-    let namespaces = api.list(&Default::default()).await.unwrap();
+    let namespaces = api.list(&selection.list_params()).await.unwrap();
     for ns in &namespaces.items {
         // for the list of all Namespace, check if the tag values matches with the specific namespace
         // For example: metadata.name = backend is matching with the Namespace manifest
         // Invoke the trait JMSExpression default parse method. Namespace implements trait JMSExpression
-        let result = ns.parse(expression).await?;
+        let result = selection.matches(ns).await?;
         if result {
             // upscale hpa
             let hpa_api: Api<HorizontalPodAutoscaler> =
@@ -160,6 +389,12 @@ pub async fn upscale_ns(
             cj_api
                 .controller_upscale_resource_items(None, client.clone())
                 .await?;
+            // upscale argo rollout
+            let ro_api: Api<Rollout> =
+                Api::namespaced(client.clone(), ns.metadata.name.as_ref().unwrap());
+            ro_api
+                .controller_upscale_resource_items(replicas, client.clone())
+                .await?;
         }
     }
     Ok(())
@@ -1,16 +1,12 @@
+use crate::clock::SystemClock;
 use crate::controller::{finalizer, upscaler, Upscaler};
 use crate::error::Error;
 use crate::parser::ContextData;
 use kube::{Resource, ResourceExt};
 use kube_runtime::controller::Action;
 use log::error;
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::time::Duration;
-#[derive(Serialize, Deserialize, Debug)]
-enum Value {
-    String,
-}
 
 #[cfg(not(tarpaulin_include))]
 pub fn on_error(_object: Arc<Upscaler>, error: &Error, _context: Arc<ContextData>) -> Action {
@@ -52,43 +48,86 @@ pub async fn reconcile(
             finalizer::add(client.clone(), &name, &namespace).await?;
             // Invoke creation of a Kubernetes built-in resource named deployment with `n` Upscaler service pods.
             // loop thru the scale
+            let mut is_pending = false;
             for res in &upscaler.spec.scale {
+                // scheduled resources are upscaled only when the schedule is in uptime
+                if !res.is_uptime(&SystemClock)? {
+                    is_pending = true;
+                    continue;
+                }
+                let selection = res
+                    .selection(client.clone(), &format!("Upscaler {}/{}", namespace, name))
+                    .await?;
                 // for each resources in spec
                 for r in &res.resource {
                     let f = check_input_resource(r);
                     if let Some(f) = f {
                         match f {
                             Resources::Deployment => {
-                                upscaler::upscale_deploy(
-                                    client.clone(),
-                                    res.replicas,
-                                    &res.jmespath,
-                                )
-                                .await?
+                                upscaler::upscale_deploy(client.clone(), res.replicas, &selection)
+                                    .await?
                             }
                             Resources::StatefulSet => {
                                 upscaler::upscale_statefulset(
                                     client.clone(),
                                     res.replicas,
-                                    &res.jmespath,
+                                    &selection,
                                 )
                                 .await?
                             }
                             Resources::Namespace => {
-                                upscaler::upscale_ns(client.clone(), res.replicas, &res.jmespath)
+                                upscaler::upscale_ns(client.clone(), res.replicas, &selection)
                                     .await?
                             }
                             Resources::CronJob => {
-                                upscaler::enable_cronjob(client.clone(), &res.jmespath).await?
+                                upscaler::enable_cronjob(client.clone(), &selection).await?
                             }
                             Resources::Hpa => {
-                                upscaler::upscale_hpa(client.clone(), res.replicas, &res.jmespath)
+                                upscaler::upscale_hpa(client.clone(), res.replicas, &selection)
+                                    .await?
+                            }
+                            Resources::Rollout => {
+                                upscaler::upscale_rollout(client.clone(), res.replicas, &selection)
                                     .await?
                             }
+                            Resources::Job => {
+                                upscaler::resume_job(client.clone(), &selection).await?
+                            }
+                            Resources::DaemonSet => {
+                                upscaler::enable_daemonset(client.clone(), &selection).await?
+                            }
+                            Resources::HelmRelease => {
+                                upscaler::resume_helmrelease(client.clone(), &selection).await?
+                            }
+                            Resources::Kustomization => {
+                                upscaler::resume_kustomization(client.clone(), &selection).await?
+                            }
+                            Resources::Vpa => {
+                                upscaler::resume_vpa(client.clone(), &selection).await?
+                            }
+                            Resources::Application => {
+                                upscaler::resume_application(client.clone(), &selection).await?
+                            }
+                            Resources::ScaledObject => {
+                                upscaler::resume_scaled_object(client.clone(), &selection).await?
+                            }
+                            Resources::Scalable(ar) => {
+                                upscaler::upscale_scalable(
+                                    client.clone(),
+                                    &ar,
+                                    res.replicas,
+                                    &selection,
+                                )
+                                .await?
+                            }
                         }
                     };
                 }
             }
+            if is_pending {
+                // keep the upscaler resource until the schedule is in uptime
+                return Ok(Action::requeue(Duration::from_secs(60)));
+            }
             let api: Api<Upscaler> = Api::namespaced(client, &namespace);
             // delete the upscaler resource after creation as there is no use
             api.delete(&name, &DeleteParams::default()).await?;
@@ -114,9 +153,12 @@ fn determine_action(upscaler: &Upscaler) -> UpscalerAction {
         .meta()
         .finalizers
         .as_ref()
-        .map_or(true, |finalizers| finalizers.is_empty())
+        .is_none_or(|finalizers| finalizers.is_empty())
     {
         UpscalerAction::Create
+    } else if upscaler.spec.scale.iter().any(|res| res.is_scheduled()) {
+        // scheduled upscaler is re-evaluated until its schedule is in uptime
+        UpscalerAction::Create
     } else {
         UpscalerAction::NoOp
     }
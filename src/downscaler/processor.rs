@@ -1,20 +1,44 @@
+use crate::clock::{Clock, FixedClock, SystemClock};
+use crate::controller::{DownscaleRule, TenantSchedule};
 use crate::csv::generate_csv;
 use crate::downscaler::resource::{
-    cronjob::CJob, deployment::Deploy, hpa::Hpa, namespace::Nspace, statefulset::StateSet,
+    argocd::ArgoApplication, cronjob::CJob, daemonset::DSet, deployment::Deploy, flux::Flux,
+    hpa::Hpa, job::BatchJob, keda::KedaScaledObject, namespace::Nspace, rollout::ArgoRollout,
+    scale::Scalable, statefulset::StateSet, vpa::Vpa,
+};
+use crate::downscaler::{
+    Calendar, Exception, ExceptionAction, JmesPath, Res, Resources, Rule, Rules, ScaledResources,
+    Selection, TenantPolicy, Uptime,
 };
-use crate::downscaler::{Res, Resources, Rule, Rules};
 use crate::error::Error;
+use crate::holiday::read_calendar;
 use crate::parser::{check_input_resource, Args, CommType};
+use crate::schedule::{Schedule, Transitions, TRANSITION_HORIZON_DAYS};
 use crate::slack::Slack;
-use crate::time_check::is_uptime;
+use crate::time_check::{is_in_range, parse_local_datetime, resolve_local, Margins};
 use crate::ScaleState;
+use chrono::{DateTime, Days, NaiveTime, TimeDelta, Utc};
+use chrono_tz::Tz;
 use core::time;
-use kube::Client;
+use futures::StreamExt;
+use kube::api::{Patch, PatchParams};
+use kube::core::Selector;
+use kube::runtime::{reflector, watcher, WatchStreamExt};
+use kube::{Api, Client, ResourceExt};
 use log::{debug, error, info};
 use prometheus::register_int_counter;
-use regex::Regex;
-use std::fs::File;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
 use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+
+// how often the rules file is checked for changes, a ConfigMap update swaps the symlink of the file
+const RULES_POLL_INTERVAL: time::Duration = time::Duration::from_secs(5);
+
+/// Valid TenantSchedules as rules, by namespace
+pub(crate) type TenantRules = BTreeMap<String, Vec<Rule>>;
 
 #[derive(Clone)]
 pub struct Process {
@@ -22,6 +46,8 @@ pub struct Process {
     rules: String,
     comm_type: Option<CommType>,
     comm_detail: Option<String>,
+    clock: Arc<dyn Clock>,
+    dry_run: bool,
 }
 
 impl From<Args> for Process {
@@ -31,6 +57,11 @@ impl From<Args> for Process {
             rules: k.rules,
             comm_type: k.comm_type,
             comm_detail: k.comm_details,
+            clock: match k.now {
+                Some(now) => Arc::new(FixedClock(now)),
+                None => Arc::new(SystemClock),
+            },
+            dry_run: k.dry_run,
         }
     }
 }
@@ -57,11 +88,23 @@ impl ScaleState {
             "Total number of errors during scaleup"
         )
         .unwrap();
+        let rules_reload_success_counter = register_int_counter!(
+            "no_of_rules_reload_success",
+            "Total number of rules reloaded successfully"
+        )
+        .unwrap();
+        let rules_reload_error_counter = register_int_counter!(
+            "no_of_rules_reload_error",
+            "Total number of invalid rules rejected during reload"
+        )
+        .unwrap();
         ScaleState {
             scaledown_succcess_counter,
             scaleup_succcess_counter,
             scaleup_error_counter,
             scaledown_error_counter,
+            rules_reload_success_counter,
+            rules_reload_error_counter,
         }
     }
 }
@@ -69,205 +112,1793 @@ impl Process {
     #[cfg(not(tarpaulin_include))]
     pub async fn processor(&self, state: Arc<ScaleState>) -> Result<(), Error> {
         let interval_millis = time::Duration::from_millis(self.interval * 1000);
-        let f = File::open(&self.rules).unwrap();
-        let r: Rules = serde_yaml::from_reader(f).unwrap();
+        let mut rules = RulesFile::load(&self.rules)?;
         let client = Client::try_default().await?;
+        let mut sighup = signal(SignalKind::hangup())?;
+        // DownscaleRule resources are watched and evaluated together with the rules of the file
+        let (downscale_rules, writer) = reflector::store::<DownscaleRule>();
+        tokio::spawn(
+            reflector(
+                writer,
+                watcher(Api::all(client.clone()), watcher::Config::default()),
+            )
+            .default_backoff()
+            .for_each(|_| futures::future::ready(())),
+        );
+        // TenantSchedule resources of every namespace
+        let (tenant_schedules, writer) = reflector::store::<TenantSchedule>();
+        tokio::spawn(
+            reflector(
+                writer,
+                watcher(Api::all(client.clone()), watcher::Config::default()),
+            )
+            .default_backoff()
+            .for_each(|_| futures::future::ready(())),
+        );
+        let mut poll = tokio::time::interval(RULES_POLL_INTERVAL);
+        let mut next_run = tokio::time::Instant::now();
 
         info!(
             "Confgured to look for resource at the interval of {} secs",
             interval_millis.as_secs()
         );
         loop {
-            let ret = r
-                .process_rules(
-                    client.clone(),
-                    self.comm_type.clone(),
-                    self.comm_detail.clone(),
-                    state.clone(),
-                )
-                .await;
+            tokio::select! {
+                _ = tokio::time::sleep_until(next_run) => {
+                    let downscale_rules = downscale_rules.state();
+                    let tenants = rules
+                        .rules
+                        .tenant_rules(
+                            &tenant_schedules.state(),
+                            &downscale_rules,
+                            client.clone(),
+                            self.clock.as_ref(),
+                            self.dry_run,
+                        )
+                        .await;
+                    let ret = rules
+                        .rules
+                        .process_file_rules(
+                            &tenants,
+                            client.clone(),
+                            self.comm_type.clone(),
+                            self.comm_detail.clone(),
+                            state.clone(),
+                            self.clock.as_ref(),
+                            self.dry_run,
+                        )
+                        .await;
 
-            match ret {
-                Ok(a) => a,
-                Err(e) => {
-                    // dont break the loop/process, just report the error to stdout
-                    error!("Error: {}", e.to_string());
+                    match ret {
+                        Ok(a) => a,
+                        Err(e) => {
+                            // dont break the loop/process, just report the error to stdout
+                            error!("Failed to process the rules of {} : {}", self.rules, e);
+                        }
+                    };
+                    let ret = rules
+                        .rules
+                        .process_downscale_rules(
+                            &downscale_rules,
+                            &tenants,
+                            client.clone(),
+                            self.comm_type.clone(),
+                            self.comm_detail.clone(),
+                            state.clone(),
+                            self.clock.as_ref(),
+                            self.dry_run,
+                        )
+                        .await;
+                    if let Err(e) = ret {
+                        error!("Failed to process the DownscaleRules : {}", e);
+                    }
+                    next_run = tokio::time::Instant::now() + interval_millis;
                 }
-            };
-            tokio::time::sleep(interval_millis).await;
+                _ = sighup.recv() => {
+                    info!("SIGHUP received, reloading the rules {}", self.rules);
+                    rules.reload_and_report(true, &state);
+                }
+                _ = poll.tick() => rules.reload_and_report(false, &state),
+            }
+        }
+    }
+}
+
+/// Rules of the rules file, reloaded when the file changes or on SIGHUP
+struct RulesFile {
+    path: String,
+    // contents of the last reload, compared through the path so that a symlink swap is a change
+    contents: String,
+    // contents of the holiday calendars of the rules at the last reload
+    calendars: Vec<String>,
+    rules: Rules,
+}
+
+impl RulesFile {
+    fn load(path: &str) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        let rules = Rules::from_yaml(&contents, path)?;
+        let mut rules_file = RulesFile {
+            path: path.to_owned(),
+            contents,
+            calendars: vec![],
+            rules,
+        };
+        rules_file.calendars = rules_file.calendar_contents();
+        Ok(rules_file)
+    }
+
+    // a calendar which can't be read is compared as empty, reloading reports the error
+    fn calendar_contents(&self) -> Vec<String> {
+        let base = Path::new(&self.path)
+            .parent()
+            .unwrap_or_else(|| Path::new("."));
+        self.rules
+            .calendars
+            .iter()
+            .map(|c| fs::read_to_string(base.join(&c.path)).unwrap_or_default())
+            .collect()
+    }
+
+    /// Swaps in the rules when the file or one of its calendars changed, or always when forced, and
+    /// returns whether they were swapped. Invalid rules are rejected and the last good rules are kept
+    fn reload(&mut self, force: bool) -> Result<bool, Error> {
+        let contents = fs::read_to_string(&self.path)?;
+        let calendars = self.calendar_contents();
+        if !force && contents == self.contents && calendars == self.calendars {
+            return Ok(false);
+        }
+        // invalid contents are reported once, until the files change again
+        self.contents = contents;
+        self.calendars = calendars;
+        self.rules = Rules::from_yaml(&self.contents, &self.path)?;
+        self.calendars = self.calendar_contents();
+        Ok(true)
+    }
+
+    fn reload_and_report(&mut self, force: bool, state: &ScaleState) {
+        match self.reload(force) {
+            Ok(true) => {
+                info!(
+                    "Reloaded {} rules from {}",
+                    self.rules.rules.len(),
+                    self.path
+                );
+                state.rules_reload_success_counter.inc();
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!("Keeping the last good rules, failed to reload: {}", e);
+                state.rules_reload_error_counter.inc();
+            }
         }
     }
 }
 
 #[allow(unused_variables)]
 impl Rules {
+    /// Reads the rules yaml and the holiday calendars referred in it
+    pub fn from_file(path: &str) -> Result<Rules, Error> {
+        Self::from_yaml(&fs::read_to_string(path)?, path)
+    }
+
+    /// Parses and validates the rules yaml read from path, the calendars are resolved from its directory
+    fn from_yaml(contents: &str, path: &str) -> Result<Rules, Error> {
+        let mut r: Rules = serde_yaml::from_str(contents)
+            .map_err(|e| Error::UserInputError(format!("Invalid rules {}: {}", path, e)))?;
+        for rule in &mut r.rules {
+            rule.expression = rule.parse_selection()?;
+        }
+        let base = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+        for calendar in &mut r.calendars {
+            calendar.dates = read_calendar(&base.join(&calendar.path))?;
+            info!(
+                "Loaded {} holidays from calendar {}",
+                calendar.dates.len(),
+                calendar.name
+            );
+        }
+        Ok(r)
+    }
+
+    /// Returns true if the date of now in the rule's timezone is a holiday in any calendar of the rule
+    fn is_holiday(&self, rule: &Rule, now: DateTime<Utc>) -> Result<bool, Error> {
+        if rule.ignore_holidays.unwrap_or(false) {
+            return Ok(false);
+        }
+        let names = rule.holidays.as_deref().unwrap_or_default();
+        for name in names {
+            if !self.calendars.iter().any(|c| &c.name == name) {
+                return Err(Error::UserInputError(format!(
+                    "holiday calendar {} is not defined for rule id {}",
+                    name, rule.id
+                )));
+            }
+        }
+        let calendars: Vec<&Calendar> = self
+            .calendars
+            .iter()
+            .filter(|c| c.default || names.contains(&c.name))
+            .collect();
+        if calendars.is_empty() {
+            return Ok(false);
+        }
+        let tz: Tz = rule.schedule.timezone().ok_or_else(|| {
+            Error::UserInputError(format!("timezone is not defined for rule id {}", rule.id))
+        })?;
+        let today = now.with_timezone(&tz).date_naive();
+        Ok(calendars.iter().any(|c| {
+            let is_holiday = c.dates.contains(&today);
+            if is_holiday {
+                debug!(
+                    "{} is a holiday in calendar {} for rule id {}",
+                    today, c.name, rule.id
+                );
+            }
+            is_holiday
+        }))
+    }
+
+    /// Returns true if the rule is up at now, an active exception overrides the schedule and
+    /// holidays are always a downtime
+    fn is_rule_uptime(&self, rule: &Rule, now: DateTime<Utc>) -> Result<bool, Error> {
+        match rule.active_exception(now)? {
+            Some(exception) => Ok(exception.action == ExceptionAction::Up),
+            None => Ok(rule.schedule.is_uptime(now)? && !self.is_holiday(rule, now)?),
+        }
+    }
+
+    /// Returns true if the resources of the rule in a namespace with the tenant schedules are up at
+    /// now, the precedence of the rule decides between its schedule and the tenant schedules
+    fn is_scope_uptime(
+        &self,
+        rule: &Rule,
+        tenants: &[Rule],
+        now: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        let is_uptime = self.is_rule_uptime(rule, now)?;
+        match rule.tenants {
+            Some(policy) if !tenants.is_empty() => {
+                let tenants_uptime = tenants
+                    .iter()
+                    .map(|t| self.is_rule_uptime(t, now))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let within_bound = match &rule.tenant_bound {
+                    Some(bound) => bound.is_uptime(now)?,
+                    None => true,
+                };
+                Ok(policy.combine(is_uptime, &tenants_uptime, within_bound))
+            }
+            _ => Ok(is_uptime),
+        }
+    }
+
+    /// Returns the next upscale and downscale of the rule after now, including its holidays and exceptions
+    pub(crate) fn next_transitions(
+        &self,
+        rule: &Rule,
+        now: DateTime<Utc>,
+    ) -> Result<Transitions, Error> {
+        self.scope_transitions(rule, &[], now)
+    }
+
+    /// Returns the next upscale and downscale of the rule combined with the tenant schedules after now
+    fn scope_transitions(
+        &self,
+        rule: &Rule,
+        tenants: &[Rule],
+        now: DateTime<Utc>,
+    ) -> Result<Transitions, Error> {
+        let mut edges = rule.transition_edges(now)?;
+        for tenant in tenants {
+            edges.extend(tenant.transition_edges(now)?);
+        }
+        if let Some(bound) = &rule.tenant_bound {
+            edges.extend(bound.edges(now, now + Days::new(TRANSITION_HORIZON_DAYS)));
+        }
+        Transitions::search(now, edges, |dt| self.is_scope_uptime(rule, tenants, dt))
+    }
+
+    /// Returns when the tenant schedule is first up outside the tenant_max_uptime of the rule within
+    /// the transition horizon, while the rule itself doesn't keep the resources up
+    fn tenant_clamping(
+        &self,
+        rule: &Rule,
+        tenant: &Rule,
+        now: DateTime<Utc>,
+    ) -> Result<Option<String>, Error> {
+        let (Some(bound), Some(max_uptime)) = (&rule.tenant_bound, &rule.tenant_max_uptime) else {
+            return Ok(None);
+        };
+        let mut candidates = tenant.transition_edges(now)?;
+        candidates.extend(bound.edges(now, now + Days::new(TRANSITION_HORIZON_DAYS)));
+        candidates.push(now);
+        candidates.sort();
+        candidates.dedup();
+        for candidate in candidates.into_iter().filter(|c| *c >= now) {
+            // windows are up after the start, so evaluate right after the candidate
+            let at = candidate + TimeDelta::seconds(1);
+            let rule_up =
+                rule.tenants == Some(TenantPolicy::Relax) && self.is_rule_uptime(rule, at)?;
+            if !rule_up && self.is_rule_uptime(tenant, at)? && !bound.is_uptime(at)? {
+                let tz = tenant.schedule.timezone().unwrap_or(Tz::UTC);
+                return Ok(Some(format!(
+                    "uptime clamped to tenant_max_uptime {} of rule id {} from {}",
+                    max_uptime,
+                    rule.id,
+                    candidate.with_timezone(&tz).format("%a %Y-%m-%d %H:%M %Z")
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Evaluates the TenantSchedules, reports their own state in their status and returns the
+    /// valid ones by namespace, an invalid schedule leaves its namespace to the rules
+    pub(crate) async fn tenant_rules(
+        &self,
+        tenant_schedules: &[Arc<TenantSchedule>],
+        downscale_rules: &[Arc<DownscaleRule>],
+        client: Client,
+        clock: &dyn Clock,
+        dry_run: bool,
+    ) -> TenantRules {
+        let now = clock.now();
+        let applied_rules: Vec<Rule> = self
+            .rules
+            .iter()
+            .filter(|r| r.tenants.is_some())
+            .cloned()
+            .chain(
+                downscale_rules
+                    .iter()
+                    .filter_map(|r| r.rule().ok())
+                    .filter(|r| r.tenants.is_some()),
+            )
+            .collect();
+        let applied_rule_ids: Vec<String> = applied_rules.iter().map(|r| r.id.clone()).collect();
+        let mut tenants = TenantRules::new();
+        for tenant_schedule in tenant_schedules {
+            let name = tenant_schedule.name_any();
+            let namespace = tenant_schedule.namespace().unwrap_or_default();
+            let mut status = tenant_schedule.status.clone().unwrap_or_default();
+            status.last_evaluation = Some(now.to_rfc3339());
+            status.applied_rules = applied_rule_ids.clone();
+            let evaluation = tenant_schedule.rule().and_then(|rule| {
+                let is_uptime = self.is_rule_uptime(&rule, now)?;
+                let transitions = self.next_transitions(&rule, now)?;
+                let clamped = applied_rules
+                    .iter()
+                    .filter_map(|r| self.tenant_clamping(r, &rule, now).transpose())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((rule, is_uptime, transitions, clamped))
+            });
+            match evaluation {
+                Ok((rule, is_uptime, transitions, clamped)) => {
+                    let next_transition = transitions
+                        .describe_next(is_uptime, rule.schedule.timezone().unwrap_or(Tz::UTC));
+                    debug!(
+                        "tenant schedule {} is {}, {}",
+                        rule.id,
+                        if is_uptime { "up" } else { "down" },
+                        next_transition
+                    );
+                    // the schedule still applies, within the bounds of the rules
+                    status.error = None;
+                    status.clamped = match clamped.is_empty() {
+                        true => None,
+                        false => {
+                            info!("tenant schedule {} {}", rule.id, clamped.join(", "));
+                            Some(clamped.join(", "))
+                        }
+                    };
+                    status.state = Some(if is_uptime { "up" } else { "down" }.to_owned());
+                    status.next_transition = Some(next_transition);
+                    tenants.entry(namespace.clone()).or_default().push(rule);
+                }
+                Err(e) => {
+                    error!("Invalid TenantSchedule {}/{} : {}", namespace, name, e);
+                    status.error = Some(e.to_string());
+                    status.clamped = None;
+                }
+            }
+            if dry_run {
+                continue;
+            }
+            let api: Api<TenantSchedule> = Api::namespaced(client.clone(), &namespace);
+            let patch = json!({ "status": status });
+            if let Err(e) = api
+                .patch_status(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await
+            {
+                error!(
+                    "failed to update the status of TenantSchedule {}/{}, {}",
+                    namespace, name, e
+                );
+            }
+        }
+        tenants
+    }
+
     pub async fn process_rules(
         &self,
         client: Client,
         comm_type: Option<CommType>,
         comm_detail: Option<String>,
         state: Arc<ScaleState>,
+        clock: &dyn Clock,
+        dry_run: bool,
     ) -> Result<(), Error> {
+        self.process_file_rules(
+            &TenantRules::new(),
+            client,
+            comm_type,
+            comm_detail,
+            state,
+            clock,
+            dry_run,
+        )
+        .await
+    }
+
+    /// Scales the resources of the rules of the file, with the tenant schedules of the namespaces
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn process_file_rules(
+        &self,
+        tenants: &TenantRules,
+        client: Client,
+        comm_type: Option<CommType>,
+        comm_detail: Option<String>,
+        state: Arc<ScaleState>,
+        clock: &dyn Clock,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        // evaluate all the rules at the same instant
+        let now = clock.now();
         for e in &self.rules {
-            debug!(
-                "Checking if the current timestamp is in the uptime slot {} for the rule id {}",
-                e.uptime, e.id
-            );
-            // check if the resource needs to be up
-            let is_uptime = match e.validate_uptime() {
-                Ok(is_uptrue) => is_uptrue,
-                Err(er) => {
-                    error!("Error while reading rule id {} : {} ", e.id, er);
-                    // don't break the loop
-                    continue;
-                }
+            // a rule failing at runtime, for example on a jmespath error, doesn't stop the others
+            if let Err(er) = self
+                .process_rule_with_tenants(
+                    e,
+                    tenants,
+                    now,
+                    client.clone(),
+                    &comm_type,
+                    &comm_detail,
+                    Arc::clone(&state),
+                    dry_run,
+                )
+                .await
+            {
+                error!("Failed to process rule id {} : {}", e.id, er);
+            }
+        }
+        Ok(())
+    }
+
+    /// Scales the resources of the rule, when the rule accepts tenant schedules the resources of each
+    /// namespace with tenant schedules are scaled separately with the combined schedule
+    #[allow(clippy::too_many_arguments)]
+    async fn process_rule_with_tenants(
+        &self,
+        e: &Rule,
+        tenants: &TenantRules,
+        now: DateTime<Utc>,
+        client: Client,
+        comm_type: &Option<CommType>,
+        comm_detail: &Option<String>,
+        state: Arc<ScaleState>,
+        dry_run: bool,
+    ) -> Result<RuleEvaluation, Error> {
+        // the namespaces of the namespace selector are listed once for all the resources of the
+        // rule and the namespaces of the tenants
+        let selection = Selection::new(
+            client.clone(),
+            e.expression.as_ref(),
+            e.selector.as_ref(),
+            e.namespace_selector.as_ref(),
+        )
+        .await?;
+        if e.tenants.is_none() || tenants.is_empty() {
+            return self
+                .process_rule(
+                    e,
+                    &selection,
+                    &Scope::All,
+                    now,
+                    client,
+                    comm_type,
+                    comm_detail,
+                    state,
+                    dry_run,
+                )
+                .await;
+        }
+        // the namespaces of the tenants are excluded from the rule's own schedule
+        let mut evaluation = self
+            .process_rule(
+                e,
+                &selection,
+                &Scope::Except(tenants),
+                now,
+                client.clone(),
+                comm_type,
+                comm_detail,
+                Arc::clone(&state),
+                dry_run,
+            )
+            .await?;
+        for (namespace, tenant_rules) in tenants {
+            let scoped = Rule {
+                id: format!("{}@{}", e.id, namespace),
+                ..e.clone()
             };
+            let scoped_evaluation = self
+                .process_rule(
+                    &scoped,
+                    &selection,
+                    &Scope::Tenant(namespace, tenant_rules),
+                    now,
+                    client.clone(),
+                    comm_type,
+                    comm_detail,
+                    Arc::clone(&state),
+                    dry_run,
+                )
+                .await?;
+            if let (
+                RuleEvaluation::Evaluated { scaled, .. },
+                RuleEvaluation::Evaluated {
+                    scaled: scoped_scaled,
+                    ..
+                },
+            ) = (&mut evaluation, scoped_evaluation)
+            {
+                scaled.extend(scoped_scaled);
+            }
+        }
+        Ok(evaluation)
+    }
 
-            debug!("uptime for rule id {} is currently {}", e.uptime, is_uptime);
-            // for each resource in rules.yaml
-            for r in &e.resource {
-                let f = check_input_resource(r);
-                if let Some(f) = f {
-                    info!("Processing rule {} for {}", e.id, r);
-                    let state = Arc::clone(&state);
-
-                    let resoure_list = match f {
-                        Resources::Hpa => {
-                            let h = Hpa::new(&e.jmespath, e.replicas, is_uptime);
-                            h.downscale(client.clone(), state).await?
-                        }
-                        Resources::Deployment => {
-                            let d = Deploy::new(&e.jmespath, e.replicas, is_uptime);
-                            d.downscale(client.clone(), state).await?
-                        }
-                        Resources::Namespace => {
-                            let n = Nspace::new(&e.jmespath, e.replicas, is_uptime);
-                            n.downscale(client.clone(), state).await?
-                        }
-                        Resources::StatefulSet => {
-                            let s = StateSet::new(&e.jmespath, e.replicas, is_uptime);
-                            s.downscale(client.clone(), state).await?
-                        }
-                        Resources::CronJob => {
-                            let c = CJob::new(&e.jmespath, is_uptime);
-                            c.downscale(client.clone(), state).await?
+    /// Scales the resources of the selection of the rule in the scope up or down at now
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn process_rule(
+        &self,
+        e: &Rule,
+        selection: &Selection,
+        scope: &Scope<'_>,
+        now: DateTime<Utc>,
+        client: Client,
+        comm_type: &Option<CommType>,
+        comm_detail: &Option<String>,
+        state: Arc<ScaleState>,
+        dry_run: bool,
+    ) -> Result<RuleEvaluation, Error> {
+        debug!(
+            "Checking if the current timestamp is in the uptime slot {} for the rule id {}",
+            e.schedule_description(),
+            e.id
+        );
+        if let Ok(Some(exception)) = e.active_exception(now) {
+            info!(
+                "exception {} from {} to {} {} is active for rule id {}, forcing {:?}",
+                exception.name,
+                exception.start,
+                exception.end,
+                exception.timezone,
+                e.id,
+                exception.action
+            );
+        }
+        // check if the resource needs to be up
+        let tenants = scope.tenants();
+        let (is_uptime, transitions) = match self
+            .is_scope_uptime(e, tenants, now)
+            .and_then(|is_uptime| Ok((is_uptime, self.scope_transitions(e, tenants, now)?)))
+        {
+            Ok(uptime) => uptime,
+            Err(er) => {
+                error!("Error while reading rule id {} : {} ", e.id, er);
+                // don't break the loop
+                return Ok(RuleEvaluation::Invalid(er));
+            }
+        };
+        let next_transition =
+            transitions.describe_next(is_uptime, e.schedule.timezone().unwrap_or(Tz::UTC));
+        info!(
+            "rule id {} is {}, {}",
+            e.id,
+            if is_uptime { "up" } else { "down" },
+            next_transition
+        );
+
+        debug!("uptime for rule id {} is currently {}", e.id, is_uptime);
+        if dry_run {
+            info!(
+                "dry-run: rule id {} would scale {} {:?} at {}",
+                e.id,
+                if is_uptime { "up" } else { "down" },
+                e.resource,
+                now
+            );
+            return Ok(RuleEvaluation::Evaluated {
+                is_uptime,
+                next_transition,
+                scaled: vec![],
+            });
+        }
+        let selection = selection.clone().within(scope);
+        let mut scaled = vec![];
+        // for each resource in rules.yaml
+        for r in &e.resource {
+            let f = check_input_resource(r);
+            if let Some(f) = f {
+                info!("Processing rule {} for {}", e.id, r);
+                let state = Arc::clone(&state);
+
+                let resoure_list = match f {
+                    Resources::Hpa => {
+                        let h = Hpa::new(&selection, e.replicas, is_uptime);
+                        h.downscale(client.clone(), state).await?
+                    }
+                    Resources::Vpa => {
+                        let v = Vpa::new(&selection, is_uptime);
+                        v.downscale(client.clone(), state).await?
+                    }
+                    Resources::Deployment => {
+                        let d = Deploy::new(
+                            &selection,
+                            e.replicas,
+                            is_uptime,
+                            e.suspend_flux,
+                            e.pause_argocd,
+                        );
+                        d.downscale(client.clone(), state).await?
+                    }
+                    Resources::Namespace => {
+                        let n = Nspace::new(
+                            &selection,
+                            e.replicas,
+                            is_uptime,
+                            e.active_jobs,
+                            e.pause_argocd,
+                        );
+                        n.downscale(client.clone(), state).await?
+                    }
+                    Resources::StatefulSet => {
+                        let s = StateSet::new(
+                            &selection,
+                            e.replicas,
+                            is_uptime,
+                            e.suspend_flux,
+                            e.pause_argocd,
+                        );
+                        s.downscale(client.clone(), state).await?
+                    }
+                    Resources::CronJob => {
+                        let c = CJob::new(&selection, is_uptime, e.active_jobs);
+                        c.downscale(client.clone(), state).await?
+                    }
+                    Resources::Job => {
+                        let j = BatchJob::new(&selection, is_uptime);
+                        j.downscale(client.clone(), state).await?
+                    }
+                    Resources::Rollout => {
+                        let r = ArgoRollout::new(&selection, e.replicas, is_uptime);
+                        r.downscale(client.clone(), state).await?
+                    }
+                    Resources::DaemonSet => {
+                        let d = DSet::new(&selection, is_uptime);
+                        d.downscale(client.clone(), state).await?
+                    }
+                    Resources::HelmRelease | Resources::Kustomization => {
+                        let f = Flux::new(f, &selection, is_uptime);
+                        f.downscale(client.clone(), state).await?
+                    }
+                    Resources::Application => {
+                        let a = ArgoApplication::new(&selection, is_uptime);
+                        a.downscale(client.clone(), state).await?
+                    }
+                    Resources::ScaledObject => {
+                        let k = KedaScaledObject::new(&selection, e.replicas, is_uptime);
+                        k.downscale(client.clone(), state).await?
+                    }
+                    Resources::Scalable(ar) => {
+                        let s = Scalable::new(&ar, &selection, e.replicas, is_uptime);
+                        match s.downscale(client.clone(), state).await {
+                            Ok(list) => list,
+                            Err(err) => {
+                                // like an unsupported resource type, continue with the next resource
+                                error!("rule id {}: failed to scale {}, {}", e.id, r, err);
+                                continue;
+                            }
                         }
-                    };
-                    // Send the alert only if resources are scaled down or upped
-                    if !resoure_list.is_empty() {
-                        if let Some(ref comm) = comm_type {
-                            match comm {
-                                CommType::Slack => {
-                                    // if channel is defined in rules only
-                                    if let Some(ref channel) = e.slack_channel {
-                                        generate_csv(&resoure_list, &e.id)?;
-                                        let slack_channel = &e.slack_channel;
-                                        let token = comm.get_secret().unwrap();
-                                        let comment = slack_alert_initial_comment(&e.id, is_uptime);
-
-                                        let s = Slack::new(
-                                            &comment,
-                                            channel,
-                                            &e.id,
-                                            "KubeSaverAlert.csv",
-                                            comm_detail.as_ref().unwrap(),
-                                            &token,
-                                        );
-                                        s.send_slack_msg().await?
-                                    }
+                    }
+                };
+                // Send the alert only if resources are scaled down or upped
+                if !resoure_list.is_empty() {
+                    if let Some(comm) = comm_type {
+                        match comm {
+                            CommType::Slack => {
+                                // if channel is defined in rules only
+                                if let Some(ref channel) = e.slack_channel {
+                                    generate_csv(&resoure_list, &e.id)?;
+                                    let slack_channel = &e.slack_channel;
+                                    let token = comm.get_secret().unwrap();
+                                    let comment = slack_alert_initial_comment(
+                                        &e.id,
+                                        is_uptime,
+                                        &next_transition,
+                                    );
+
+                                    let s = Slack::new(
+                                        &comment,
+                                        channel,
+                                        &e.id,
+                                        "KubeSaverAlert.csv",
+                                        comm_detail.as_ref().unwrap(),
+                                        &token,
+                                    );
+                                    s.send_slack_msg().await?
                                 }
                             }
                         }
                     }
                 }
+                scaled.extend(resoure_list);
+            }
+        }
+        Ok(RuleEvaluation::Evaluated {
+            is_uptime,
+            next_transition,
+            scaled,
+        })
+    }
+
+    /// Evaluates the DownscaleRule resources like the rules of the file, with the calendars of the
+    /// file and the tenant schedules, and reports the evaluation in their status
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn process_downscale_rules(
+        &self,
+        downscale_rules: &[Arc<DownscaleRule>],
+        tenants: &TenantRules,
+        client: Client,
+        comm_type: Option<CommType>,
+        comm_detail: Option<String>,
+        state: Arc<ScaleState>,
+        clock: &dyn Clock,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let now = clock.now();
+        let api: Api<DownscaleRule> = Api::all(client.clone());
+        for downscale_rule in downscale_rules {
+            let name = downscale_rule.name_any();
+            let mut status = downscale_rule.status.clone().unwrap_or_default();
+            status.last_evaluation = Some(now.to_rfc3339());
+            let evaluation = match downscale_rule.rule() {
+                Ok(rule) => {
+                    // a failing DownscaleRule is reported in its status, the others are still evaluated
+                    match self
+                        .process_rule_with_tenants(
+                            &rule,
+                            tenants,
+                            now,
+                            client.clone(),
+                            &comm_type,
+                            &comm_detail,
+                            Arc::clone(&state),
+                            dry_run,
+                        )
+                        .await
+                    {
+                        Ok(evaluation) => evaluation,
+                        Err(e) => {
+                            error!("Failed to process DownscaleRule {} : {}", name, e);
+                            RuleEvaluation::Invalid(e)
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Invalid DownscaleRule {} : {}", name, e);
+                    RuleEvaluation::Invalid(e)
+                }
+            };
+            match evaluation {
+                RuleEvaluation::Invalid(e) => status.error = Some(e.to_string()),
+                RuleEvaluation::Evaluated {
+                    is_uptime,
+                    next_transition,
+                    scaled,
+                } => {
+                    status.error = None;
+                    status.state = Some(if is_uptime { "up" } else { "down" }.to_owned());
+                    status.next_transition = Some(next_transition);
+                    // the resources of the last transition are kept until the next one
+                    if !scaled.is_empty() {
+                        status.matched_resources = scaled
+                            .iter()
+                            .map(|r| format!("{} {}/{}", r.kind, r.namespace, r.name))
+                            .collect();
+                    }
+                }
+            }
+            if dry_run {
+                continue;
+            }
+            let patch = json!({ "status": status });
+            if let Err(e) = api
+                .patch_status(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await
+            {
+                error!(
+                    "failed to update the status of DownscaleRule {}, {}",
+                    name, e
+                );
             }
         }
         Ok(())
     }
 }
 
+// schedule of a single window or of the windows sharing one timezone
+fn uptime_schedule(uptime: &Uptime, margins: Margins) -> Result<Schedule, Error> {
+    match uptime {
+        Uptime::Window(window) => Schedule::windows(std::slice::from_ref(window), margins),
+        Uptime::Windows { timezone, windows } => Schedule::windows(
+            &windows
+                .iter()
+                .map(|w| format!("{} {}", w, timezone))
+                .collect::<Vec<_>>(),
+            margins,
+        ),
+    }
+}
+
+impl TenantPolicy {
+    /// Uptime of the resources of a rule in a namespace from the uptime of the rule and of the
+    /// tenant schedules of the namespace, the uptime added by the tenants is clamped to the
+    /// tenant_max_uptime of the rule
+    fn combine(self, is_uptime: bool, tenants_uptime: &[bool], within_bound: bool) -> bool {
+        let tenants_up = within_bound && tenants_uptime.iter().any(|up| *up);
+        match self {
+            TenantPolicy::Tighten => is_uptime && tenants_uptime.iter().all(|up| *up),
+            TenantPolicy::Relax => is_uptime || tenants_up,
+            TenantPolicy::Replace => tenants_up,
+        }
+    }
+}
+
+/// Namespaces of the resources of a rule, split by the TenantSchedules when the rule accepts them
+pub(crate) enum Scope<'a> {
+    All,
+    // the namespaces without tenant schedules
+    Except(&'a TenantRules),
+    // a namespace and its tenant schedules
+    Tenant(&'a str, &'a [Rule]),
+}
+
+impl Scope<'_> {
+    fn tenants(&self) -> &[Rule] {
+        match self {
+            Scope::Tenant(_, tenants) => tenants,
+            _ => &[],
+        }
+    }
+}
+
+impl Selection {
+    /// Restricts the selected resources to the namespaces of the scope
+    fn within(mut self, scope: &Scope) -> Self {
+        match scope {
+            Scope::All => {}
+            Scope::Except(tenants) => self.excluded_namespaces = tenants.keys().cloned().collect(),
+            Scope::Tenant(namespace, _) => {
+                let namespace = namespace.to_string();
+                self.namespaces = Some(match self.namespaces {
+                    // outside the namespaces of the namespace selector
+                    Some(namespaces) if !namespaces.contains(&namespace) => BTreeSet::new(),
+                    _ => BTreeSet::from([namespace]),
+                });
+            }
+        }
+        self
+    }
+}
+
+/// Outcome of a rule at an evaluation
+pub(crate) enum RuleEvaluation {
+    // the schedule, holidays or exceptions of the rule could not be evaluated
+    Invalid(Error),
+    Evaluated {
+        is_uptime: bool,
+        next_transition: String,
+        // resources scaled up or down by the evaluation
+        scaled: Vec<ScaledResources>,
+    },
+}
+
 impl Rule {
-    /// Returns true if its a uptime
-    fn validate_uptime(&self) -> Result<bool, Error> {
-        let m = match Regex::new(
-            r"^([a-zA-Z]{3})-([a-zA-Z]{3}) (\d\d):(\d\d)-(\d\d):(\d\d) (?P<tz>[a-zA-Z/_]+)$",
-        ) {
-            Ok(value) => match value.is_match(&self.uptime) {
-                true => {
-                    let m = value.captures(&self.uptime).unwrap();
-                    is_uptime(m)
+    /// Validates the label selectors and compiles the jmespath, the rule must select its resources
+    /// with at least one of them
+    pub(crate) fn parse_selection(&self) -> Result<Option<JmesPath>, Error> {
+        let expression = match self.jmespath.as_str() {
+            "" if self.selector.is_none() && self.namespace_selector.is_none() => {
+                return Err(Error::UserInputError(format!(
+                    "rule id {} must define jmespath, selector or namespaceSelector",
+                    self.id
+                )));
+            }
+            "" => None,
+            jmespath => Some(JmesPath::compile(
+                &format!("rule id {}", self.id),
+                jmespath,
+            )?),
+        };
+        for selector in self.selector.iter().chain(&self.namespace_selector) {
+            Selector::try_from(selector.clone()).map_err(|e| {
+                Error::UserInputError(format!(
+                    "Invalid label selector for rule id {}: {}",
+                    self.id, e
+                ))
+            })?;
+        }
+        Ok(expression)
+    }
+
+    /// Parses and validates either the uptime or the upschedule/downschedule of the rule
+    pub(crate) fn parse_schedule(&self) -> Result<Schedule, Error> {
+        let in_rule = |e: Error| match e {
+            Error::UserInputError(e) => {
+                Error::UserInputError(format!("rule id {}: {}", self.id, e))
+            }
+            e => e,
+        };
+        let margins =
+            Margins::parse(self.prewarm.as_deref(), self.grace.as_deref()).map_err(in_rule)?;
+        match (&self.upschedule, &self.downschedule) {
+            (Some(upschedule), Some(downschedule)) => {
+                if self.uptime.is_some() {
+                    return Err(Error::UserInputError(format!(
+                        "rule id {} must define either uptime or upschedule/downschedule, not both",
+                        self.id
+                    )));
                 }
-                false => Err(Error::UserInputError(String::from("Input datetime format didn't match <DAY>-<DAY> <START_TIME_HR>:<START_TIME_MIN>-<END_TIME_HR>:<END_TIME_MIN> <TIMEZONE>, Refer sample example in README.md"))),
-            },
-            Err(e) => Err(Error::UserInputError(e.to_string())),
+                let timezone = self.timezone.as_ref().ok_or_else(|| {
+                    Error::UserInputError(format!(
+                        "timezone is required with upschedule/downschedule for rule id {}",
+                        self.id
+                    ))
+                })?;
+                return Schedule::cron(upschedule, downschedule, timezone, margins)
+                    .map_err(in_rule);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Error::UserInputError(format!(
+                    "rule id {} must define both upschedule and downschedule",
+                    self.id
+                )))
+            }
+        }
+        match &self.uptime {
+            None => Err(Error::UserInputError(format!(
+                "rule id {} must define either uptime or upschedule/downschedule",
+                self.id
+            ))),
+            Some(uptime) => uptime_schedule(uptime, margins).map_err(in_rule),
+        }
+    }
+
+    /// Parses the tenant_max_uptime window, only the rules relaxed or replaced by the
+    /// TenantSchedules accept it
+    pub(crate) fn parse_tenant_bound(&self) -> Result<Option<Schedule>, Error> {
+        let Some(uptime) = &self.tenant_max_uptime else {
+            return Ok(None);
         };
-        m
+        if !matches!(
+            self.tenants,
+            Some(TenantPolicy::Relax | TenantPolicy::Replace)
+        ) {
+            return Err(Error::UserInputError(format!(
+                "rule id {} sets tenant_max_uptime, which requires tenants: relax or replace",
+                self.id
+            )));
+        }
+        uptime_schedule(uptime, Margins::default())
+            .map(Some)
+            .map_err(|e| match e {
+                Error::UserInputError(e) => {
+                    Error::UserInputError(format!("rule id {}: tenant_max_uptime {}", self.id, e))
+                }
+                e => e,
+            })
+    }
+
+    /// Instants after now where the uptime of the rule may change, its schedule edges, midnights
+    /// for the holidays and the bounds of its exceptions
+    fn transition_edges(&self, now: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>, Error> {
+        let mut edges = self
+            .schedule
+            .edges(now, now + Days::new(TRANSITION_HORIZON_DAYS));
+        // holidays start and end at midnight in the rule's timezone
+        if let Some(tz) = self.schedule.timezone() {
+            let today = now.with_timezone(&tz).date_naive();
+            edges.extend(
+                today
+                    .iter_days()
+                    .take(TRANSITION_HORIZON_DAYS as usize + 1)
+                    .map(|date| resolve_local(&tz, date.and_time(NaiveTime::MIN)).to_utc()),
+            );
+        }
+        for exception in self.exceptions.iter().flatten() {
+            let tz: Tz = exception.timezone.parse()?;
+            edges.push(parse_local_datetime(&exception.start, &tz)?.to_utc());
+            edges.push(parse_local_datetime(&exception.end, &tz)?.to_utc());
+        }
+        Ok(edges)
+    }
+
+    /// Returns the first exception whose date range includes now
+    fn active_exception(&self, now: DateTime<Utc>) -> Result<Option<&Exception>, Error> {
+        for exception in self.exceptions.iter().flatten() {
+            if is_in_range(&exception.start, &exception.end, &exception.timezone, now).map_err(
+                |e| {
+                    Error::UserInputError(format!(
+                        "exception {} of rule id {}: {}",
+                        exception.name, self.id, e
+                    ))
+                },
+            )? {
+                return Ok(Some(exception));
+            }
+        }
+        Ok(None)
+    }
+
+    fn schedule_description(&self) -> String {
+        match (&self.uptime, &self.upschedule, &self.downschedule) {
+            (Some(uptime), _, _) => uptime.to_string(),
+            (None, Some(upschedule), Some(downschedule)) => format!(
+                "upschedule {} downschedule {} {}",
+                upschedule,
+                downschedule,
+                self.timezone.as_deref().unwrap_or_default()
+            ),
+            _ => String::new(),
+        }
     }
 }
 
-fn slack_alert_initial_comment(id: &str, up_time: bool) -> String {
+fn slack_alert_initial_comment(id: &str, up_time: bool, next_transition: &str) -> String {
     let mut event = "Down";
     if up_time {
         event = "Up";
     }
-    format!("Scaling {} event completed for rule id {}", event, &id)
+    format!(
+        "Scaling {} event completed for rule id {}, {}",
+        event, &id, next_transition
+    )
 }
 
 #[test]
 fn validate_up_slack_alert_initial_comment() {
     assert_eq!(
-        slack_alert_initial_comment("scaledown-kube-id", true),
-        "Scaling Up event completed for rule id scaledown-kube-id"
+        slack_alert_initial_comment(
+            "scaledown-kube-id",
+            true,
+            "next downscale at Mon 2024-09-09 19:00 AEST"
+        ),
+        "Scaling Up event completed for rule id scaledown-kube-id, next downscale at Mon 2024-09-09 19:00 AEST"
     )
 }
 
 #[test]
 fn validate_down_slack_alert_initial_comment() {
     assert_eq!(
-        slack_alert_initial_comment("scaledown-kube-id", false),
-        "Scaling Down event completed for rule id scaledown-kube-id"
+        slack_alert_initial_comment(
+            "scaledown-kube-id",
+            false,
+            "next upscale at Mon 2024-09-09 07:00 AEST"
+        ),
+        "Scaling Down event completed for rule id scaledown-kube-id, next upscale at Mon 2024-09-09 07:00 AEST"
     )
 }
 
 #[test]
 fn validate_invalid_datetime_regex() {
     let r = Rule {
-        uptime: String::from("blah"),
+        id: String::from("regex"),
+        uptime: Some(Uptime::Window(String::from("blah"))),
         ..Default::default()
     };
-    let uptime = r.validate_uptime();
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
     assert_eq!(
         uptime.unwrap_err().to_string(),
-        "Invalid User Input: Input datetime format didn't match <DAY>-<DAY> <START_TIME_HR>:<START_TIME_MIN>-<END_TIME_HR>:<END_TIME_MIN> <TIMEZONE>, Refer sample example in README.md".to_string()
+        "Invalid User Input: rule id regex: Input datetime format didn't match <DAY>-<DAY> <START_TIME_HR>:<START_TIME_MIN>-<END_TIME_HR>:<END_TIME_MIN> <TIMEZONE>, Refer sample example in README.md".to_string()
     )
 }
 
 #[test]
 fn validate_should_be_uptime_regex() {
     let r = Rule {
-        uptime: String::from("Mon-Sun 00:00-23:59 Australia/Sydney"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 00:00-23:59 Australia/Sydney",
+        ))),
         ..Default::default()
     };
-    let uptime = r.validate_uptime();
-    assert_eq!(uptime.unwrap(), true);
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.unwrap());
 }
 
 #[test]
 fn validate_invalid_timezone_regex() {
     let r = Rule {
-        uptime: String::from("Mon-Sun 00:00-23:59 India/Sydney"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 00:00-23:59 India/Sydney",
+        ))),
         ..Default::default()
     };
-    let uptime = r.validate_uptime();
-    assert_eq!(uptime.is_err(), true);
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.is_err());
 }
 
 #[test]
 fn validate_should_be_downtime_regex() {
     let r = Rule {
-        uptime: String::from("Mon-Sun 23:58-23:59 Australia/Sydney"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 23:58-23:59 Australia/Sydney",
+        ))),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(!uptime.unwrap());
+}
+
+#[test]
+fn validate_should_be_uptime_multiple_windows() {
+    let r = Rule {
+        uptime: Some(Uptime::Windows {
+            timezone: String::from("Australia/Sydney"),
+            windows: vec![
+                String::from("Mon-Sun 23:58-23:59"),
+                String::from("Mon-Sun 00:00-23:59"),
+            ],
+        }),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.unwrap());
+}
+
+#[test]
+fn validate_should_be_downtime_multiple_windows() {
+    let r = Rule {
+        uptime: Some(Uptime::Windows {
+            timezone: String::from("Australia/Sydney"),
+            windows: vec![
+                String::from("Mon-Sun 23:58-23:59"),
+                String::from("Mon-Sun 23:57-23:58"),
+            ],
+        }),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(!uptime.unwrap());
+}
+
+#[test]
+fn validate_invalid_window_in_multiple_windows() {
+    let r = Rule {
+        uptime: Some(Uptime::Windows {
+            timezone: String::from("Australia/Sydney"),
+            windows: vec![String::from("Mon-Sun 00:00-23:59"), String::from("blah")],
+        }),
         ..Default::default()
     };
-    let uptime = r.validate_uptime();
-    assert_eq!(uptime.unwrap(), false);
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.is_err());
+}
+
+#[test]
+fn validate_uptime_deserialize_single_and_multiple_windows() {
+    let rules: Rules = serde_yaml::from_str(
+        r#"
+rules:
+  - id: single
+    uptime: Mon-Fri 07:00-19:00 Australia/Sydney
+    jmespath: "metadata.name == 'kuber'"
+    resource:
+      - Namespace
+  - id: multiple
+    uptime:
+      timezone: Australia/Sydney
+      windows:
+        - Mon-Fri 07:00-19:00
+        - Sat-Sat 09:00-13:00
+    jmespath: "metadata.name == 'kuber'"
+    resource:
+      - Namespace
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        rules.rules[0].uptime,
+        Some(Uptime::Window(String::from(
+            "Mon-Fri 07:00-19:00 Australia/Sydney"
+        )))
+    );
+    assert_eq!(
+        rules.rules[1].uptime.as_ref().unwrap().to_string(),
+        "Mon-Fri 07:00-19:00, Sat-Sat 09:00-13:00 Australia/Sydney"
+    );
+}
+
+#[test]
+fn validate_should_be_uptime_cron_schedule() {
+    let r = Rule {
+        upschedule: Some(String::from("* * * * *")),
+        downschedule: Some(String::from("0 0 1 1 *")),
+        timezone: Some(String::from("Australia/Sydney")),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.unwrap());
+}
+
+#[test]
+fn validate_cron_schedule_without_timezone() {
+    let r = Rule {
+        id: String::from("cron"),
+        upschedule: Some(String::from("0 7 * * MON-FRI")),
+        downschedule: Some(String::from("0 19 * * MON-FRI")),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert_eq!(
+        uptime.unwrap_err().to_string(),
+        "Invalid User Input: timezone is required with upschedule/downschedule for rule id cron"
+    );
+}
+
+#[test]
+fn validate_cron_schedule_with_uptime() {
+    let r = Rule {
+        id: String::from("cron"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 00:00-23:59 Australia/Sydney",
+        ))),
+        upschedule: Some(String::from("0 7 * * MON-FRI")),
+        downschedule: Some(String::from("0 19 * * MON-FRI")),
+        timezone: Some(String::from("Australia/Sydney")),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.is_err());
+}
+
+#[test]
+fn validate_cron_schedule_missing_downschedule() {
+    let r = Rule {
+        id: String::from("cron"),
+        upschedule: Some(String::from("0 7 * * MON-FRI")),
+        timezone: Some(String::from("Australia/Sydney")),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert_eq!(
+        uptime.unwrap_err().to_string(),
+        "Invalid User Input: rule id cron must define both upschedule and downschedule"
+    );
+}
+
+#[test]
+fn validate_holiday_is_downtime() {
+    let today = Utc::now()
+        .with_timezone(&chrono_tz::Australia::Sydney)
+        .date_naive();
+    let r = Rules {
+        calendars: vec![
+            Calendar {
+                name: String::from("au"),
+                dates: [today].into_iter().collect(),
+                ..Default::default()
+            },
+            Calendar {
+                name: String::from("company"),
+                default: true,
+                ..Default::default()
+            },
+        ],
+        rules: vec![],
+    };
+    let mut rule = Rule {
+        id: String::from("holiday"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 00:00-23:59 Australia/Sydney",
+        ))),
+        holidays: Some(vec![String::from("au")]),
+        ..Default::default()
+    };
+    rule.schedule = rule.parse_schedule().unwrap();
+    assert!(r.is_holiday(&rule, Utc::now()).unwrap());
+    // opt out of the holidays
+    rule.ignore_holidays = Some(true);
+    assert!(!r.is_holiday(&rule, Utc::now()).unwrap());
+    // only the default calendar applies
+    rule.ignore_holidays = None;
+    rule.holidays = None;
+    assert!(!r.is_holiday(&rule, Utc::now()).unwrap());
+    // calendar must be defined
+    rule.holidays = Some(vec![String::from("blah")]);
+    assert_eq!(
+        r.is_holiday(&rule, Utc::now()).unwrap_err().to_string(),
+        "Invalid User Input: holiday calendar blah is not defined for rule id holiday"
+    );
+}
+
+#[test]
+fn validate_rules_from_file_with_calendars() {
+    let dir = std::env::temp_dir().join("kube-saver-calendars");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("holidays.yaml"), "- 2024-12-25\n").unwrap();
+    std::fs::write(
+        dir.join("rules.yaml"),
+        r#"
+calendars:
+  - name: au
+    path: holidays.yaml
+    default: true
+rules:
+  - id: holiday
+    uptime: Mon-Fri 07:00-19:00 Australia/Sydney
+    jmespath: "metadata.name == 'kuber'"
+    resource:
+      - Namespace
+"#,
+    )
+    .unwrap();
+    let r = Rules::from_file(dir.join("rules.yaml").to_str().unwrap()).unwrap();
+    assert!(r.calendars[0]
+        .dates
+        .contains(&chrono::NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn validate_rules_reload() {
+    let rules = |id: &str, jmespath: &str| {
+        format!(
+            "rules:\n  - id: {}\n    uptime: Mon-Fri 07:00-19:00 Australia/Sydney\n    jmespath: \"{}\"\n    resource:\n      - Namespace\n",
+            id, jmespath
+        )
+    };
+    let dir = std::env::temp_dir().join("kube-saver-reload");
+    let _ = std::fs::remove_dir_all(&dir);
+    // a ConfigMap mount links the file through ..data to a timestamped directory
+    for version in ["v1", "v2"] {
+        std::fs::create_dir_all(dir.join(version)).unwrap();
+    }
+    std::fs::write(
+        dir.join("v1/rules.yaml"),
+        rules("first", "metadata.name == 'kuber'"),
+    )
+    .unwrap();
+    std::os::unix::fs::symlink("v1", dir.join("..data")).unwrap();
+    std::os::unix::fs::symlink("..data/rules.yaml", dir.join("rules.yaml")).unwrap();
+
+    let mut r = RulesFile::load(dir.join("rules.yaml").to_str().unwrap()).unwrap();
+    assert!(!r.reload(false).unwrap());
+    assert!(r.reload(true).unwrap());
+
+    // invalid rules are rejected and the last good rules are kept
+    std::fs::write(
+        dir.join("v1/rules.yaml"),
+        rules("second", "metadata.name =="),
+    )
+    .unwrap();
+    assert!(r
+        .reload(false)
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid User Input: Invalid jmespath for rule id second"));
+    assert_eq!(r.rules.rules[0].id, "first");
+    assert!(!r.reload(false).unwrap());
+
+    // swapping the symlink is a change
+    std::fs::write(
+        dir.join("v2/rules.yaml"),
+        rules("third", "metadata.name == 'kuber'"),
+    )
+    .unwrap();
+    std::fs::remove_file(dir.join("..data")).unwrap();
+    std::os::unix::fs::symlink("v2", dir.join("..data")).unwrap();
+    assert!(r.reload(false).unwrap());
+    assert_eq!(r.rules.rules[0].id, "third");
+
+    // editing a calendar of the rules is a change
+    std::fs::write(dir.join("v2/holidays.yaml"), "- 2024-12-25\n").unwrap();
+    std::os::unix::fs::symlink("..data/holidays.yaml", dir.join("holidays.yaml")).unwrap();
+    std::fs::write(
+        dir.join("v2/rules.yaml"),
+        format!(
+            "calendars:\n  - name: au\n    path: holidays.yaml\n{}",
+            rules("fourth", "metadata.name == 'kuber'")
+        ),
+    )
+    .unwrap();
+    assert!(r.reload(false).unwrap());
+    assert!(!r.reload(false).unwrap());
+    std::fs::write(dir.join("v2/holidays.yaml"), "- 2024-12-26\n").unwrap();
+    assert!(r.reload(false).unwrap());
+    assert!(r.rules.calendars[0]
+        .dates
+        .contains(&chrono::NaiveDate::from_ymd_opt(2024, 12, 26).unwrap()));
+    std::fs::write(dir.join("v2/holidays.yaml"), "- 2024-13-26\n").unwrap();
+    assert!(r.reload(false).is_err());
+    assert!(!r.reload(false).unwrap());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn validate_should_be_uptime_week_days_list() {
+    let r = Rule {
+        uptime: Some(Uptime::Window(String::from(
+            "Mon,Tue,Wed-Fri,Sat-Sun 00:00-23:59 Australia/Sydney",
+        ))),
+        ..Default::default()
+    };
+    let uptime = r.parse_schedule().and_then(|s| s.is_uptime(Utc::now()));
+    assert!(uptime.unwrap());
+}
+
+#[test]
+fn validate_active_exception() {
+    let r = Rule {
+        id: String::from("exception"),
+        exceptions: Some(vec![
+            Exception {
+                name: String::from("past-release"),
+                start: String::from("2020-06-01 00:00"),
+                end: String::from("2020-06-08 00:00"),
+                timezone: String::from("Australia/Sydney"),
+                action: ExceptionAction::Up,
+            },
+            Exception {
+                name: String::from("freeze"),
+                start: String::from("2020-06-01 00:00"),
+                end: String::from("2999-06-08 00:00"),
+                timezone: String::from("Australia/Sydney"),
+                action: ExceptionAction::Down,
+            },
+        ]),
+        ..Default::default()
+    };
+    let exception = r.active_exception(Utc::now()).unwrap().unwrap();
+    assert_eq!(exception.name, "freeze");
+    assert_eq!(exception.action, ExceptionAction::Down);
+}
+
+#[test]
+fn validate_no_active_exception() {
+    let r = Rule {
+        exceptions: Some(vec![Exception {
+            name: String::from("past-release"),
+            start: String::from("2020-06-01 00:00"),
+            end: String::from("2020-06-08 00:00"),
+            timezone: String::from("Australia/Sydney"),
+            action: ExceptionAction::Up,
+        }]),
+        ..Default::default()
+    };
+    assert_eq!(r.active_exception(Utc::now()).unwrap(), None);
+}
+
+#[test]
+fn validate_invalid_exception() {
+    let r = Rule {
+        id: String::from("exception"),
+        exceptions: Some(vec![Exception {
+            name: String::from("release"),
+            start: String::from("2020-06-01"),
+            end: String::from("2020-06-08 00:00"),
+            timezone: String::from("Australia/Sydney"),
+            action: ExceptionAction::Up,
+        }]),
+        ..Default::default()
+    };
+    assert!(r
+        .active_exception(Utc::now())
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid User Input: exception release of rule id exception:"));
+}
+
+#[test]
+fn validate_invalid_prewarm() {
+    let r = Rule {
+        id: "rule-prewarm".to_string(),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Sun 00:00-23:59 Australia/Sydney",
+        ))),
+        prewarm: Some("soon".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        r.parse_schedule().unwrap_err().to_string(),
+        "Invalid User Input: rule id rule-prewarm: Invalid duration soon, expected for example 15m or 1h30m"
+    );
+}
+
+#[test]
+fn validate_invalid_rules_rejected_when_deserialized() {
+    let rules: Result<Rules, _> = serde_yaml::from_str(
+        r#"
+rules:
+  - id: invalid-day
+    uptime: Mon-Fry 07:00-19:00 Australia/Sydney
+    jmespath: "metadata.name == 'kuber'"
+    resource:
+      - Namespace
+"#,
+    );
+    assert!(rules.unwrap_err().to_string().starts_with(
+        "Invalid User Input: rule id invalid-day: uptime Mon-Fry 07:00-19:00 Australia/Sydney: Invalid day Fry"
+    ));
+    let rules: Result<Rules, _> = serde_yaml::from_str(
+        r#"
+rules:
+  - id: invalid-cron
+    upschedule: 0 7 * * MON-FRI
+    downschedule: 0 19 * * MON-FRY
+    timezone: Australia/Sydney
+    jmespath: "metadata.name == 'kuber'"
+    resource:
+      - Namespace
+"#,
+    );
+    assert!(rules.unwrap_err().to_string().starts_with(
+        "Invalid User Input: rule id invalid-cron: Invalid cron expression 0 19 * * MON-FRY"
+    ));
+}
+
+#[test]
+fn validate_active_jobs() {
+    use crate::downscaler::ActiveJobs;
+    let rules: Rules = serde_yaml::from_str(
+        r#"
+rules:
+  - id: cronjob-delete-jobs
+    uptime: Mon-Fri 07:00-19:00 Australia/Sydney
+    jmespath: "metadata.labels.app == 'backup'"
+    resource:
+      - CronJob
+    active_jobs: delete
+  - id: cronjob
+    uptime: Mon-Fri 07:00-19:00 Australia/Sydney
+    jmespath: "metadata.labels.app == 'report'"
+    resource:
+      - CronJob
+"#,
+    )
+    .unwrap();
+    assert_eq!(rules.rules[0].active_jobs, Some(ActiveJobs::Delete));
+    assert_eq!(rules.rules[1].active_jobs, None);
+}
+
+#[test]
+fn validate_uptime_at_fixed_clock() {
+    let r = Rule {
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Fri 07:00-19:00 Australia/Sydney",
+        ))),
+        ..Default::default()
+    };
+    let schedule = r.parse_schedule().unwrap();
+    // Friday 18:59 in Sydney
+    let clock = FixedClock(crate::clock::parse_rfc3339("2024-09-06T18:59:00+10:00").unwrap());
+    assert!(schedule.is_uptime(clock.now()).unwrap());
+    // Friday 19:01 in Sydney
+    let clock = FixedClock(crate::clock::parse_rfc3339("2024-09-06T19:01:00+10:00").unwrap());
+    assert!(!schedule.is_uptime(clock.now()).unwrap());
+}
+
+#[test]
+fn validate_next_transitions_with_holidays_and_exceptions() {
+    use crate::clock::parse_rfc3339;
+    let r = Rules {
+        calendars: vec![Calendar {
+            name: String::from("au"),
+            default: true,
+            // Monday 09-Sep-2024
+            dates: [chrono::NaiveDate::from_ymd_opt(2024, 9, 9).unwrap()]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }],
+        rules: vec![],
+    };
+    let mut rule = Rule {
+        id: String::from("transitions"),
+        uptime: Some(Uptime::Window(String::from(
+            "Mon-Fri 07:00-19:00 Australia/Sydney",
+        ))),
+        exceptions: Some(vec![Exception {
+            name: String::from("release"),
+            start: String::from("2024-09-07 10:00"),
+            end: String::from("2024-09-07 12:00"),
+            timezone: String::from("Australia/Sydney"),
+            action: ExceptionAction::Up,
+        }]),
+        ..Default::default()
+    };
+    rule.schedule = rule.parse_schedule().unwrap();
+    // Friday 20:00 in Sydney, the exception is up on Saturday
+    let t = r
+        .next_transitions(&rule, parse_rfc3339("2024-09-06T20:00:00+10:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-07T10:00:00+10:00").unwrap())
+    );
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-07T12:00:00+10:00").unwrap())
+    );
+    // Saturday 13:00 in Sydney, Monday is a holiday
+    let t = r
+        .next_transitions(&rule, parse_rfc3339("2024-09-07T13:00:00+10:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-10T07:00:00+10:00").unwrap())
+    );
+}
+
+#[test]
+fn validate_tenant_precedence() {
+    use crate::clock::parse_rfc3339;
+    let r = Rules {
+        calendars: vec![],
+        rules: vec![],
+    };
+    let rule = |id: &str, uptime: &str, tenants: Option<TenantPolicy>| {
+        let mut rule = Rule {
+            id: id.to_owned(),
+            uptime: Some(Uptime::Window(uptime.to_owned())),
+            tenants,
+            ..Default::default()
+        };
+        rule.schedule = rule.parse_schedule().unwrap();
+        rule
+    };
+    let tenants = [rule(
+        "team-a/late-nights",
+        "Mon-Fri 09:00-23:00 Australia/Sydney",
+        None,
+    )];
+    // Friday 08:00 and 20:00 in Sydney
+    let morning = parse_rfc3339("2024-09-06T08:00:00+10:00").unwrap();
+    let evening = parse_rfc3339("2024-09-06T20:00:00+10:00").unwrap();
+    let platform = |policy| rule("office", "Mon-Fri 07:00-19:00 Australia/Sydney", policy);
+    let uptime = |policy, now| r.is_scope_uptime(&platform(policy), &tenants, now).unwrap();
+    assert_eq!(
+        [
+            uptime(Some(TenantPolicy::Tighten), morning),
+            uptime(Some(TenantPolicy::Tighten), evening)
+        ],
+        [false, false]
+    );
+    assert_eq!(
+        [
+            uptime(Some(TenantPolicy::Relax), morning),
+            uptime(Some(TenantPolicy::Relax), evening)
+        ],
+        [true, true]
+    );
+    assert_eq!(
+        [
+            uptime(Some(TenantPolicy::Replace), morning),
+            uptime(Some(TenantPolicy::Replace), evening)
+        ],
+        [false, true]
+    );
+    // tenant schedules are ignored without a precedence
+    assert_eq!(
+        [uptime(None, morning), uptime(None, evening)],
+        [true, false]
+    );
+
+    // the transitions include the edges of the tenant schedules
+    let t = r
+        .scope_transitions(&platform(Some(TenantPolicy::Relax)), &tenants, evening)
+        .unwrap();
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-06T23:00:00+10:00").unwrap())
+    );
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-09T07:00:00+10:00").unwrap())
+    );
+
+    // the uptime added by the tenants is clamped to tenant_max_uptime
+    let bounded = |policy| {
+        let mut rule = platform(Some(policy));
+        rule.tenant_max_uptime = Some(Uptime::Window(
+            "Mon-Fri 06:00-21:00 Australia/Sydney".to_owned(),
+        ));
+        rule.tenant_bound = rule.parse_tenant_bound().unwrap();
+        rule
+    };
+    let night = parse_rfc3339("2024-09-06T22:00:00+10:00").unwrap();
+    let bounded_uptime = |policy, now| r.is_scope_uptime(&bounded(policy), &tenants, now).unwrap();
+    assert_eq!(
+        [
+            bounded_uptime(TenantPolicy::Relax, evening),
+            bounded_uptime(TenantPolicy::Relax, night),
+            bounded_uptime(TenantPolicy::Replace, night)
+        ],
+        [true, false, false]
+    );
+    let t = r
+        .scope_transitions(&bounded(TenantPolicy::Relax), &tenants, evening)
+        .unwrap();
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-06T21:00:00+10:00").unwrap())
+    );
+    assert_eq!(
+        r.tenant_clamping(&bounded(TenantPolicy::Relax), &tenants[0], morning)
+            .unwrap(),
+        Some("uptime clamped to tenant_max_uptime Mon-Fri 06:00-21:00 Australia/Sydney of rule id office from Fri 2024-09-06 21:00 AEST".to_owned())
+    );
+    assert_eq!(
+        r.tenant_clamping(&platform(Some(TenantPolicy::Relax)), &tenants[0], morning)
+            .unwrap(),
+        None
+    );
+    let mut tightened = bounded(TenantPolicy::Relax);
+    tightened.tenants = Some(TenantPolicy::Tighten);
+    assert_eq!(
+        tightened.parse_tenant_bound().unwrap_err().to_string(),
+        "Invalid User Input: rule id office sets tenant_max_uptime, which requires tenants: relax or replace"
+    );
+
+    // the namespaces of the tenants are scaled separately
+    let tenant_rules = TenantRules::from([("team-a".to_owned(), tenants.to_vec())]);
+    let selection = Selection::default().within(&Scope::Except(&tenant_rules));
+    assert_eq!(
+        selection.excluded_namespaces,
+        BTreeSet::from(["team-a".to_owned()])
+    );
+    let selection = Selection::default().within(&Scope::Tenant("team-a", &tenants));
+    assert_eq!(
+        selection.namespaces,
+        Some(BTreeSet::from(["team-a".to_owned()]))
+    );
+    // the resources of a tenant are listed in its namespace only
+    assert_eq!(selection.single_namespace(), Some("team-a"));
+    assert_eq!(Selection::default().single_namespace(), None);
+}
+
+#[test]
+fn validate_selection_of_rules() {
+    let rules = |selector: &str| -> Rules {
+        serde_yaml::from_str(&format!(
+            "rules:\n  - id: selectors\n    uptime: Mon-Fri 07:00-19:00 Australia/Sydney\n{}    resource:\n      - Deployment\n",
+            selector
+        ))
+        .unwrap()
+    };
+    let validate = |selector: &str| rules(selector).rules[0].parse_selection();
+    assert!(validate("    selector:\n      matchLabels:\n        app: web\n").is_ok());
+    assert!(validate("    namespaceSelector:\n      matchLabels:\n        team: a\n").is_ok());
+    assert_eq!(
+        validate("").unwrap_err().to_string(),
+        "Invalid User Input: rule id selectors must define jmespath, selector or namespaceSelector"
+    );
+    assert_eq!(
+        validate("    selector:\n      matchExpressions:\n        - key: app\n          operator: Equals\n").unwrap_err().to_string(),
+        "Invalid User Input: Invalid label selector for rule id selectors: failed to parse value as expression: Invalid expression operator"
+    );
 }
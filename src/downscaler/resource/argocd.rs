@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{client::Client, Api, CustomResource, ResourceExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::common::{list_or_empty, ScalingMachinery};
+
+/// syncPolicy.automated of the application before self-heal was paused, as json
+pub const ORIGINAL_SYNC_POLICY_ANNOTATION: &str = "kubesaver.com/original_sync_policy";
+
+// label set by argocd on the objects of an application, `<name>` or `<namespace>_<name>`
+const INSTANCE_LABEL: &str = "argocd.argoproj.io/instance";
+
+/// ArgoCD Application `argoproj.io/v1alpha1`, only the sync policy is typed
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "argoproj.io",
+    version = "v1alpha1",
+    kind = "Application",
+    plural = "applications",
+    namespaced,
+    schema = "disabled"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_policy: Option<Value>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+impl Application {
+    // automated sync of the application, argocd re-applies the replicas while it is set
+    fn automated(&self) -> Option<Value> {
+        self.spec
+            .sync_policy
+            .as_ref()?
+            .get("automated")
+            .filter(|a| !a.is_null())
+            .cloned()
+    }
+}
+
+impl JMSExpression for Application {}
+
+/// Original syncPolicy.automated saved in the annotations when the application was paused
+pub(crate) fn original_sync_policy(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> Option<Value> {
+    serde_json::from_str(annotations?.get(ORIGINAL_SYNC_POLICY_ANNOTATION)?).ok()
+}
+
+/// Spec patch of the application, removes the automated sync at downtime and restores it at uptime
+pub(crate) fn sync_policy_patch(automated: Option<Value>) -> Value {
+    json!({ "syncPolicy": { "automated": automated.unwrap_or(Value::Null) } })
+}
+
+fn app_machinery(app: &Application, scale_state: Arc<ScaleState>) -> ScalingMachinery {
+    ScalingMachinery {
+        tobe_replicas: None,                // doesn't apply to application
+        original_replicas: "0".to_string(), // doesn't apply to application
+        original_max_replicas: None,
+        original_policy: app.automated(),
+        name: app.name_any(),
+        namespace: app.namespace().unwrap_or_default(),
+        annotations: app.metadata.annotations.clone(),
+        resource_type: Resources::Application,
+        scale_state,
+    }
+}
+
+/// Applications by the instance label of the objects they manage
+#[derive(Debug, Default)]
+pub(crate) struct ArgoApps {
+    apps: BTreeMap<String, Application>,
+    // applications already paused or resumed in this run
+    handled: BTreeSet<(String, String)>,
+}
+
+impl ArgoApps {
+    pub(crate) async fn list(api: &Api<Application>) -> Result<Self, Error> {
+        Ok(Self::from_applications(
+            list_or_empty(api, &ListParams::default(), "ArgoCD applications")
+                .await?
+                .items,
+        ))
+    }
+
+    fn from_applications(items: Vec<Application>) -> Self {
+        let mut apps = BTreeMap::new();
+        for app in items {
+            let name = app.name_any();
+            // applications outside the argocd namespace are labelled with their namespace
+            apps.insert(
+                format!("{}_{}", app.namespace().unwrap_or_default(), name),
+                app.clone(),
+            );
+            apps.insert(name, app);
+        }
+        ArgoApps {
+            apps,
+            handled: BTreeSet::new(),
+        }
+    }
+
+    fn get(&self, labels: Option<&BTreeMap<String, String>>) -> Option<&Application> {
+        self.apps.get(labels?.get(INSTANCE_LABEL)?)
+    }
+
+    /// ArgoCD self-heal would restore the replicas of the workload, so the automated sync of the
+    /// application managing it is paused for the downtime, its result is reported as paired with the workload
+    pub(crate) async fn pause_owner(
+        &mut self,
+        c: Client,
+        labels: Option<&BTreeMap<String, String>>,
+        workload: &ScalingMachinery,
+        is_uptime: bool,
+    ) -> Result<Option<ScaledResources>, Error> {
+        self.pause(
+            c,
+            labels,
+            format!("{}/{}", workload.resource_type, workload.name),
+            Arc::clone(&workload.scale_state),
+            is_uptime,
+        )
+        .await
+    }
+
+    /// Pauses the applications managing the deployments and statefulsets of the namespace, for the
+    /// Namespace rules
+    pub(crate) async fn pause_namespace_owners(
+        &mut self,
+        c: Client,
+        namespace: &str,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let deployments: Api<Deployment> = Api::namespaced(c.clone(), namespace);
+        let statefulsets: Api<StatefulSet> = Api::namespaced(c.clone(), namespace);
+        let workloads = deployments
+            .list(&ListParams::default())
+            .await?
+            .items
+            .into_iter()
+            .map(|d| (Resources::Deployment, d.metadata))
+            .chain(
+                statefulsets
+                    .list(&ListParams::default())
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|s| (Resources::StatefulSet, s.metadata)),
+            );
+        let mut list_app: Vec<ScaledResources> = vec![];
+        for (resource_type, metadata) in workloads {
+            let paired_with = format!("{}/{}", resource_type, metadata.name.unwrap_or_default());
+            if let Some(scaled_res) = self
+                .pause(
+                    c.clone(),
+                    metadata.labels.as_ref(),
+                    paired_with,
+                    Arc::clone(&scale_state),
+                    is_uptime,
+                )
+                .await?
+            {
+                list_app.push(scaled_res);
+            }
+        }
+        Ok(list_app)
+    }
+
+    // pauses the application of the labels once per run, reported as paired with the workload
+    async fn pause(
+        &mut self,
+        c: Client,
+        labels: Option<&BTreeMap<String, String>>,
+        paired_with: String,
+        scale_state: Arc<ScaleState>,
+        is_uptime: bool,
+    ) -> Result<Option<ScaledResources>, Error> {
+        let Some(app) = self.get(labels) else {
+            return Ok(None);
+        };
+        // nothing to pause without automated sync
+        if !is_uptime && app.automated().is_none() {
+            return Ok(None);
+        }
+        let pat = app_machinery(app, scale_state);
+        if !self
+            .handled
+            .insert((pat.namespace.clone(), pat.name.clone()))
+        {
+            return Ok(None);
+        }
+        let scaled_res = pat.scaling_machinery(c, is_uptime).await?;
+        Ok(scaled_res.map(|r| ScaledResources {
+            paired_with: Some(paired_with),
+            ..r
+        }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArgoApplication<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> ArgoApplication<'a> {
+    pub fn new(selection: &'a Selection, is_uptime: bool) -> Self {
+        ArgoApplication {
+            selection,
+            is_uptime,
+        }
+    }
+}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for ArgoApplication<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<Application> = self.selection.api(c.clone());
+        let list =
+            list_or_empty(&api, &self.selection.list_params(), "ArgoCD applications").await?;
+        let mut list_app: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            if !self.is_uptime && item.automated().is_none() {
+                continue;
+            }
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = app_machinery(&item, Arc::clone(&scale_state));
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_app.push(scaled_res);
+                }
+            }
+        }
+        Ok(list_app)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<Application> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching application: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "ArgoCD applications").await?;
+        let mut list_app: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            if !is_uptime && item.automated().is_none() {
+                continue;
+            }
+            let pat = app_machinery(&item, Arc::clone(&scale_state));
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_app.push(scaled_res);
+            }
+        }
+        Ok(list_app)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let list = list_or_empty(self, &ListParams::default(), "ArgoCD applications").await?;
+        for app in &list.items {
+            debug!("parsing application resource {:?}", app.metadata.name);
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: app.name_any(),
+                namespace: app.namespace().unwrap_or_default(),
+                annotations: app.metadata.annotations.to_owned(),
+                resource_type: Resources::Application,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_sync_policy() {
+    let app = |name: &str, namespace: &str, sync_policy: Value| -> Application {
+        serde_json::from_value(json!({
+            "apiVersion": "argoproj.io/v1alpha1",
+            "kind": "Application",
+            "metadata": { "name": name, "namespace": namespace },
+            "spec": { "project": "default", "syncPolicy": sync_policy }
+        }))
+        .unwrap()
+    };
+    let automated = json!({ "prune": true, "selfHeal": true });
+    let web = app("web", "argocd", json!({ "automated": automated }));
+    assert_eq!(web.automated(), Some(automated.clone()));
+    assert_eq!(app("manual", "argocd", json!({})).automated(), None);
+    // the automated policy is removed at downtime and restored from the annotation at uptime
+    assert_eq!(
+        sync_policy_patch(None),
+        json!({ "syncPolicy": { "automated": null } })
+    );
+    let annotations = BTreeMap::from([(
+        ORIGINAL_SYNC_POLICY_ANNOTATION.to_string(),
+        automated.to_string(),
+    )]);
+    assert_eq!(
+        sync_policy_patch(original_sync_policy(Some(&annotations))),
+        json!({ "syncPolicy": { "automated": { "prune": true, "selfHeal": true } } })
+    );
+    assert_eq!(original_sync_policy(None), None);
+
+    let apps = ArgoApps::from_applications(vec![web, app("db", "team-a", json!({}))]);
+    let instance = |value: &str| BTreeMap::from([(INSTANCE_LABEL.to_string(), value.to_string())]);
+    let name = |app: Option<&Application>| app.map(|a| a.name_any());
+    assert_eq!(
+        name(apps.get(Some(&instance("web")))),
+        Some("web".to_owned())
+    );
+    assert_eq!(
+        name(apps.get(Some(&instance("team-a_db")))),
+        Some("db".to_owned())
+    );
+    assert_eq!(name(apps.get(Some(&instance("api")))), None);
+    assert_eq!(name(apps.get(None)), None);
+}
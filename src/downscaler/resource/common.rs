@@ -1,10 +1,26 @@
-use kube::client::Client;
-use log::info;
+use kube::api::{ListParams, ObjectList};
+use kube::{client::Client, Api, Resource};
+use log::{debug, info};
+use serde::de::DeserializeOwned;
 use serde_json::{json, Map, Value};
-use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, fmt::Debug, sync::Arc};
 
 use crate::{
-    downscaler::{Resources, ScaledResources},
+    downscaler::{
+        resource::{
+            argocd::{original_sync_policy, sync_policy_patch, ORIGINAL_SYNC_POLICY_ANNOTATION},
+            daemonset::{
+                node_selector_patch, original_node_selector, ORIGINAL_NODE_SELECTOR_ANNOTATION,
+            },
+            hpa::{
+                hpa_replicas_patch, max_replicas_annotation, original_max_replicas,
+                ORIGINAL_MAX_REPLICAS_ANNOTATION,
+            },
+            keda::PAUSED_REPLICAS_ANNOTATION,
+            vpa::{original_update_mode, update_mode_patch, ORIGINAL_UPDATE_MODE_ANNOTATION},
+        },
+        Resources, ScaleAction, ScaledResources,
+    },
     parser::dynamic_resource_type,
     ScaleState,
 };
@@ -12,9 +28,36 @@ use crate::{
 use crate::error::Error;
 use tracing::error;
 
+/// Lists the objects of a CRD which may not be installed, clusters without the CRD have no objects
+pub(crate) async fn list_or_empty<K>(
+    api: &Api<K>,
+    lp: &ListParams,
+    what: &str,
+) -> Result<ObjectList<K>, Error>
+where
+    K: Resource + Clone + DeserializeOwned + Debug,
+{
+    match api.list(lp).await {
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            debug!("CRD of the {} is not installed, skipping them", what);
+            Ok(ObjectList {
+                types: Default::default(),
+                metadata: Default::default(),
+                items: vec![],
+            })
+        }
+        list => Ok(list?),
+    }
+}
+
 pub struct ScalingMachinery {
     pub(crate) tobe_replicas: Option<i32>,
     pub(crate) original_replicas: String,
+    // maxReplicas of the hpa, saved at downtime next to the original minReplicas
+    pub(crate) original_max_replicas: Option<i32>,
+    // policy paused at downtime and restored at uptime, syncPolicy.automated of the argocd
+    // application or updatePolicy.updateMode of the vpa
+    pub(crate) original_policy: Option<Value>,
     pub(crate) name: String,
     pub(crate) namespace: String,
     pub(crate) annotations: Option<BTreeMap<String, String>>,
@@ -56,7 +99,7 @@ impl ScalingMachinery {
                 c.clone(),
                 &self.original_replicas,
                 self.tobe_replicas,
-                "true",
+                true,
                 self.scale_state.clone(),
             )
             .await?;
@@ -74,7 +117,7 @@ impl ScalingMachinery {
                 c.clone(),
                 &scale_up.to_string(),
                 Some(scale_up),
-                "false",
+                false,
                 self.scale_state.clone(),
             )
             .await?;
@@ -86,7 +129,7 @@ impl ScalingMachinery {
             || self
                 .annotations
                 .as_ref()
-                .map_or(true, |a| a.get("kubesaver.com/is_downscaled").is_none())
+                .is_none_or(|a| a.get("kubesaver.com/is_downscaled").is_none())
     }
 
     pub async fn scaling_machinery(
@@ -112,7 +155,7 @@ impl ScalingMachinery {
                         c.clone(),
                         &self.original_replicas,
                         self.tobe_replicas,
-                        "true",
+                        true,
                         self.scale_state.clone(),
                     )
                     .await?;
@@ -131,61 +174,117 @@ impl ScalingMachinery {
         client: Client,
         orig_count: &str,
         replicas: Option<i32>,
-        is_downscale: &str,
+        is_downscale: bool,
         scaled_state: Arc<ScaleState>,
     ) -> Result<ScaledResources, Error> {
         let mut flux_sync = "enabled";
-        if is_downscale == "true" {
+        if is_downscale {
             flux_sync = "disabled"
         }
 
-        let annotations: Value = json!({
+        let mut annotations: Value = json!({
             "annotations": {
-                "kubesaver.com/is_downscaled": is_downscale,
+                "kubesaver.com/is_downscaled": is_downscale.to_string(),
                 "kubesaver.com/original_count": orig_count,
                 "kustomize.toolkit.fluxcd.io/reconcile": flux_sync,
             }
         });
 
         let spec = match self.resource_type {
-            Resources::Deployment | Resources::Namespace | Resources::StatefulSet => {
-                json!({ "replicas": replicas.unwrap_or(0) })
-            }
+            Resources::Deployment
+            | Resources::Namespace
+            | Resources::StatefulSet
+            | Resources::Rollout
+            | Resources::Scalable(_) => Some(json!({ "replicas": replicas.unwrap_or(0) })),
             Resources::Hpa => {
-                json!({ "minReplicas": replicas.unwrap_or(1) }) // minReplicas should >=1
+                let min_replicas = replicas.unwrap_or(1);
+                if let Some(max_replicas) =
+                    max_replicas_annotation(self.original_max_replicas, is_downscale)
+                {
+                    annotations["annotations"][ORIGINAL_MAX_REPLICAS_ANNOTATION] = max_replicas;
+                }
+                match is_downscale {
+                    true => Some(hpa_replicas_patch(min_replicas, Some(min_replicas))),
+                    false => Some(hpa_replicas_patch(
+                        min_replicas,
+                        original_max_replicas(self.annotations.as_ref()),
+                    )),
+                }
             }
-            Resources::CronJob => {
-                json!(
-                     {
-                        "suspend": is_downscale.parse::<bool>().unwrap()
-                    }
-                )
+            Resources::CronJob
+            | Resources::Job
+            | Resources::HelmRelease
+            | Resources::Kustomization => Some(json!(
+                 {
+                    "suspend": is_downscale
+                }
+            )),
+            Resources::DaemonSet => match is_downscale {
+                true => {
+                    let original = self.original_policy.clone().unwrap_or_default();
+                    annotations["annotations"][ORIGINAL_NODE_SELECTOR_ANNOTATION] =
+                        json!(original.to_string());
+                    Some(node_selector_patch(&original, true))
+                }
+                false => {
+                    annotations["annotations"][ORIGINAL_NODE_SELECTOR_ANNOTATION] = Value::Null;
+                    let original = original_node_selector(self.annotations.as_ref());
+                    Some(node_selector_patch(&original.unwrap_or_default(), false))
+                }
+            },
+            Resources::Application => match is_downscale {
+                true => {
+                    annotations["annotations"][ORIGINAL_SYNC_POLICY_ANNOTATION] =
+                        json!(self.original_policy.as_ref().map(Value::to_string));
+                    Some(sync_policy_patch(None))
+                }
+                false => {
+                    annotations["annotations"][ORIGINAL_SYNC_POLICY_ANNOTATION] = Value::Null;
+                    original_sync_policy(self.annotations.as_ref())
+                        .map(|automated| sync_policy_patch(Some(automated)))
+                }
+            },
+            Resources::Vpa => match is_downscale {
+                true => {
+                    annotations["annotations"][ORIGINAL_UPDATE_MODE_ANNOTATION] =
+                        json!(self.original_policy.as_ref().and_then(Value::as_str));
+                    Some(update_mode_patch(None))
+                }
+                false => {
+                    annotations["annotations"][ORIGINAL_UPDATE_MODE_ANNOTATION] = Value::Null;
+                    original_update_mode(self.annotations.as_ref())
+                        .map(|update_mode| update_mode_patch(Some(update_mode)))
+                }
+            },
+            Resources::ScaledObject => {
+                // keda pauses at the replicas while the annotation is set and resumes once removed
+                annotations["annotations"][PAUSED_REPLICAS_ANNOTATION] = match is_downscale {
+                    true => json!(replicas.unwrap_or(0).to_string()),
+                    false => Value::Null,
+                };
+                None
             }
         };
 
         let mut patch = Map::new();
         patch.insert("metadata".to_string(), annotations);
-        patch.insert("spec".to_string(), spec);
+        if let Some(spec) = spec {
+            patch.insert("spec".to_string(), spec);
+        }
         let patch_object = Value::Object(patch);
 
-        let rs = dynamic_resource_type(client, &self.namespace, self.resource_type);
+        let rs = dynamic_resource_type(client, &self.namespace, &self.resource_type);
         //TODO: Error handling
         if let Some(rs) = rs {
             if let Err(e) = rs.patch_resource(&self.name, &patch_object).await {
                 error!("failed to patch resource {}, {}", self.resource_type, e);
                 metrics_incrementer(
-                    (
-                        ScaleType::from_str(is_downscale).unwrap(),
-                        ScaleStatus::Failed,
-                    ),
+                    (ScaleType::from(is_downscale), ScaleStatus::Failed),
                     scaled_state,
                 )
             } else {
                 metrics_incrementer(
-                    (
-                        ScaleType::from_str(is_downscale).unwrap(),
-                        ScaleStatus::Success,
-                    ),
+                    (ScaleType::from(is_downscale), ScaleStatus::Success),
                     scaled_state,
                 )
             }
@@ -193,7 +292,12 @@ impl ScalingMachinery {
         Ok(ScaledResources {
             name: self.name.to_owned(),
             namespace: self.namespace.to_owned(),
-            kind: self.resource_type,
+            kind: self.resource_type.clone(),
+            action: match is_downscale {
+                true => ScaleAction::Downscale,
+                false => ScaleAction::Upscale,
+            },
+            paired_with: None,
         })
     }
 }
@@ -217,17 +321,11 @@ enum ScaleStatus {
     Failed,
 }
 
-impl FromStr for ScaleType {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.eq("true") {
-            Ok(ScaleType::ScaleDown)
-        } else if s.eq("false") {
-            Ok(ScaleType::ScaleUp)
-        } else {
-            Err(Error::UserInputError(
-                "Scale type must be either true or false".to_string(),
-            ))
+impl From<bool> for ScaleType {
+    fn from(is_downscale: bool) -> Self {
+        match is_downscale {
+            true => ScaleType::ScaleDown,
+            false => ScaleType::ScaleUp,
         }
     }
 }
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
 use crate::controller::common::UpscaleMachinery;
-use crate::downscaler::{JMSExpression, Res, ResourceExtension, Resources, ScaledResources};
+use crate::downscaler::{
+    ActiveJobs, JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
 use crate::error::Error;
 use crate::ScaleState;
 use async_trait::async_trait;
@@ -12,19 +14,22 @@ use log::debug;
 use serde_json::Value;
 
 use super::common::ScalingMachinery;
+use super::job::scale_cronjob_jobs;
 
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct CJob<'a> {
-    pub(crate) expression: &'a str,
+    pub(crate) selection: &'a Selection,
     pub(crate) is_uptime: bool,
+    pub(crate) active_jobs: Option<ActiveJobs>,
 }
 
 #[allow(clippy::needless_lifetimes)]
 impl<'a> CJob<'a> {
-    pub fn new(expression: &'a str, is_uptime: bool) -> Self {
+    pub fn new(selection: &'a Selection, is_uptime: bool, active_jobs: Option<ActiveJobs>) -> Self {
         CJob {
-            expression,
+            selection,
             is_uptime,
+            active_jobs,
         }
     }
 }
@@ -39,18 +44,33 @@ impl<'a> Res for CJob<'a> {
         c: Client,
         scale_state: Arc<ScaleState>,
     ) -> Result<Vec<ScaledResources>, Error> {
-        let api: Api<CronJob> = Api::all(c.clone());
-        let list = api.list(&Default::default()).await.unwrap();
+        let api: Api<CronJob> = self.selection.api(c.clone());
+        let list = api.list(&self.selection.list_params()).await.unwrap();
         let mut list_cron: Vec<ScaledResources> = vec![];
         // TODO: Multiple threads
         for item in list.items {
-            let result = item.parse(self.expression).await?;
+            let result = self.selection.matches(&item).await?;
             if result {
                 let name = item.metadata.name.unwrap();
                 let namespace: String = item.metadata.namespace.unwrap();
+                if let Some(active_jobs) = self.active_jobs {
+                    list_cron.extend(
+                        scale_cronjob_jobs(
+                            c.clone(),
+                            &namespace,
+                            &name,
+                            active_jobs,
+                            self.is_uptime,
+                            Arc::clone(&scale_state),
+                        )
+                        .await?,
+                    );
+                }
                 let pat = ScalingMachinery {
                     tobe_replicas: None,                // doesn't apply to cronjob
                     original_replicas: "0".to_string(), // doesn't apply to cronjob
+                    original_max_replicas: None,
+                    original_policy: None,
                     name,
                     namespace,
                     annotations: item.metadata.annotations,
@@ -94,6 +114,8 @@ impl ResourceExtension for Api<CronJob> {
             let pat = ScalingMachinery {
                 tobe_replicas: replicas,
                 original_replicas: "0".to_string(), // doesn't apply to cronjob
+                original_max_replicas: None,
+                original_policy: None,
                 name,
                 namespace,
                 annotations: item.metadata.annotations,
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use k8s_openapi::api::apps::v1::DaemonSet;
+use kube::api::{Patch, PatchParams};
+use kube::{client::Client, Api};
+use log::debug;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::common::ScalingMachinery;
+
+/// Node selector label which no node has, the daemonset pods are evicted while it is set
+pub const NO_NODE_SELECTOR: &str = "kubesaver.com/no-node";
+
+/// spec.template.spec.nodeSelector of the daemonset before it was stopped
+pub const ORIGINAL_NODE_SELECTOR_ANNOTATION: &str = "kubesaver.com/original_node_selector";
+
+// node selector of the pod template, daemonsets without node selector run on every node
+fn node_selector(ds: &DaemonSet) -> Value {
+    json!(ds
+        .spec
+        .as_ref()
+        .and_then(|s| s.template.spec.as_ref())
+        .and_then(|s| s.node_selector.clone())
+        .unwrap_or_default())
+}
+
+/// Original node selector saved in the annotations when the daemonset was stopped
+pub(crate) fn original_node_selector(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> Option<Value> {
+    serde_json::from_str(annotations?.get(ORIGINAL_NODE_SELECTOR_ANNOTATION)?).ok()
+}
+
+/// Merge patch of the pod template node selector, the original selector is replaced by the no
+/// node label at downtime and restored at uptime
+pub(crate) fn node_selector_patch(original: &Value, is_downscale: bool) -> Value {
+    let mut node_selector = Map::new();
+    for (label, value) in original.as_object().into_iter().flatten() {
+        let value = match is_downscale {
+            true => Value::Null,
+            false => value.clone(),
+        };
+        node_selector.insert(label.to_owned(), value);
+    }
+    let no_node = match is_downscale {
+        true => json!("true"),
+        false => Value::Null,
+    };
+    node_selector.insert(NO_NODE_SELECTOR.to_owned(), no_node);
+    json!({ "template": { "spec": { "nodeSelector": node_selector } } })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct DSet<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> DSet<'a> {
+    pub fn new(selection: &'a Selection, is_uptime: bool) -> Self {
+        DSet {
+            selection,
+            is_uptime,
+        }
+    }
+}
+
+impl JMSExpression for DaemonSet {}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for DSet<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<DaemonSet> = self.selection.api(c.clone());
+        let list = api.list(&self.selection.list_params()).await?;
+        let mut list_ds: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = ScalingMachinery {
+                    tobe_replicas: None,                // doesn't apply to daemonset
+                    original_replicas: "0".to_string(), // doesn't apply to daemonset
+                    original_max_replicas: None,
+                    original_policy: Some(node_selector(&item)),
+                    name: item.metadata.name.unwrap(),
+                    namespace: item.metadata.namespace.unwrap(),
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::DaemonSet,
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_ds.push(scaled_res);
+                }
+            }
+        }
+        Ok(list_ds)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<DaemonSet> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching daemonset resource {:?}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = self.list(&Default::default()).await?;
+        let mut list_ds: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let original_node_selector = node_selector(&item);
+            let name = item.metadata.name.unwrap();
+            let namespace = item.metadata.namespace.unwrap();
+            debug!(
+                "Parsing daemonset {} since its in namespace {:?}",
+                name, namespace
+            );
+            let pat = ScalingMachinery {
+                tobe_replicas: None,                // doesn't apply to daemonset
+                original_replicas: "0".to_string(), // doesn't apply to daemonset
+                original_max_replicas: None,
+                original_policy: Some(original_node_selector),
+                name,
+                namespace,
+                annotations: item.metadata.annotations,
+                resource_type: Resources::DaemonSet,
+                scale_state: Arc::clone(&scale_state),
+            };
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_ds.push(scaled_res);
+            }
+        }
+        Ok(list_ds)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let ds_list = self.list(&Default::default()).await?;
+        for ds in &ds_list.items {
+            debug!("parsing daemonset resource {:?}", ds.metadata.name);
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: ds.metadata.name.as_ref().unwrap().to_string(),
+                namespace: ds.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: ds.metadata.annotations.to_owned(),
+                resource_type: Resources::DaemonSet,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_node_selector_patch() {
+    let ds: DaemonSet = serde_json::from_value(json!({
+        "metadata": { "name": "fluent-bit", "namespace": "logging" },
+        "spec": {
+            "selector": { "matchLabels": { "app": "fluent-bit" } },
+            "template": { "spec": {
+                "containers": [{ "name": "fluent-bit" }],
+                "nodeSelector": { "kubernetes.io/os": "linux" }
+            } }
+        }
+    }))
+    .unwrap();
+    let original = node_selector(&ds);
+    assert_eq!(original, json!({ "kubernetes.io/os": "linux" }));
+    // the original node selector is replaced by the no node label
+    assert_eq!(
+        node_selector_patch(&original, true),
+        json!({ "template": { "spec": { "nodeSelector": {
+            "kubernetes.io/os": null, "kubesaver.com/no-node": "true"
+        } } } })
+    );
+    let annotations = BTreeMap::from([(
+        ORIGINAL_NODE_SELECTOR_ANNOTATION.to_string(),
+        original.to_string(),
+    )]);
+    assert_eq!(
+        node_selector_patch(&original_node_selector(Some(&annotations)).unwrap(), false),
+        json!({ "template": { "spec": { "nodeSelector": {
+            "kubernetes.io/os": "linux", "kubesaver.com/no-node": null
+        } } } })
+    );
+    // daemonsets without node selector only get the no node label
+    assert_eq!(
+        node_selector_patch(&Value::Null, false),
+        json!({ "template": { "spec": { "nodeSelector": { "kubesaver.com/no-node": null } } } })
+    );
+}
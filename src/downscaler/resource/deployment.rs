@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
 use crate::controller::common::UpscaleMachinery;
-use crate::downscaler::{JMSExpression, Res, ResourceExtension, Resources, ScaledResources};
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
 use crate::error::Error;
 use crate::ScaleState;
 use async_trait::async_trait;
@@ -11,22 +13,36 @@ use kube::{client::Client, Api};
 use log::debug;
 use serde_json::Value;
 
+use super::argocd::ArgoApps;
 use super::common::ScalingMachinery;
+use super::flux::suspend_owner;
+use super::hpa::HpaTargets;
+use super::keda::{KedaTargets, ScaledObject};
 
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Deploy<'a> {
-    pub(crate) expression: &'a str,
+    pub(crate) selection: &'a Selection,
     pub(crate) replicas: Option<i32>,
     pub(crate) is_uptime: bool,
+    pub(crate) suspend_flux: bool,
+    pub(crate) pause_argocd: bool,
 }
 
 #[allow(clippy::needless_lifetimes)]
 impl<'a> Deploy<'a> {
-    pub fn new(expression: &'a str, replicas: Option<i32>, is_uptime: bool) -> Self {
+    pub fn new(
+        selection: &'a Selection,
+        replicas: Option<i32>,
+        is_uptime: bool,
+        suspend_flux: Option<bool>,
+        pause_argocd: Option<bool>,
+    ) -> Self {
         Deploy {
-            expression,
+            selection,
             replicas,
             is_uptime,
+            suspend_flux: suspend_flux.unwrap_or_default(),
+            pause_argocd: pause_argocd.unwrap_or_default(),
         }
     }
 }
@@ -42,23 +58,58 @@ impl<'a> Res for Deploy<'a> {
         c: Client,
         scale_state: Arc<ScaleState>,
     ) -> Result<Vec<ScaledResources>, Error> {
-        let api: Api<Deployment> = Api::all(c.clone());
-        let list = api.list(&Default::default()).await.unwrap();
+        let api: Api<Deployment> = self.selection.api(c.clone());
+        let list = api.list(&self.selection.list_params()).await.unwrap();
+        let so_api: Api<ScaledObject> = self.selection.api(c.clone());
+        let keda = KedaTargets::deployments(&so_api).await?;
+        let hpas = HpaTargets::list(&self.selection.api(c.clone())).await?;
+        // the applications are only paused when the rule opts in with pause_argocd
+        let mut argo = match self.pause_argocd {
+            true => ArgoApps::list(&Api::all(c.clone())).await?,
+            false => ArgoApps::default(),
+        };
         let mut list_dep: Vec<ScaledResources> = vec![];
         // TODO: Multiple threads
         for item in list.items {
-            let result = item.parse(self.expression).await?;
+            let result = self.selection.matches(&item).await?;
             let original_count = (item.spec.unwrap().replicas.unwrap()).to_string();
             if result {
+                let labels = item.metadata.labels;
+                let namespace = item.metadata.namespace.unwrap();
+                let (name, annotations, resource_type) = keda.resolve(
+                    &namespace,
+                    item.metadata.name.unwrap(),
+                    item.metadata.annotations,
+                    Resources::Deployment,
+                );
                 let pat = ScalingMachinery {
                     tobe_replicas: self.replicas,
                     original_replicas: original_count,
-                    name: item.metadata.name.unwrap(),
-                    namespace: item.metadata.namespace.unwrap(),
-                    annotations: item.metadata.annotations,
-                    resource_type: Resources::Deployment,
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name,
+                    namespace,
+                    annotations,
+                    resource_type,
                     scale_state: Arc::clone(&scale_state),
                 };
+                if let Some(scaled_res) = argo
+                    .pause_owner(c.clone(), labels.as_ref(), &pat, self.is_uptime)
+                    .await?
+                {
+                    list_dep.push(scaled_res);
+                }
+                if self.suspend_flux {
+                    if let Some(scaled_res) =
+                        suspend_owner(c.clone(), labels.as_ref(), &pat, self.is_uptime).await?
+                    {
+                        list_dep.push(scaled_res);
+                    }
+                }
+                if let Some(scaled_res) = hpas.scale_paired(c.clone(), &pat, self.is_uptime).await?
+                {
+                    list_dep.push(scaled_res);
+                }
                 if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
                     list_dep.push(scaled_res);
                 };
@@ -86,6 +137,8 @@ impl ResourceExtension for Api<Deployment> {
     ) -> Result<Vec<ScaledResources>, Error> {
         let list = self.list(&Default::default()).await?;
         let mut list_dep: Vec<ScaledResources> = vec![];
+        let so_api: Api<ScaledObject> = Api::all(c.clone());
+        let keda = KedaTargets::deployments(&so_api).await?;
         for item in list.items {
             let name = item.metadata.name.unwrap();
             let namespace = item.metadata.namespace.unwrap();
@@ -94,13 +147,21 @@ impl ResourceExtension for Api<Deployment> {
                 name, namespace
             );
             let original_count = (item.spec.unwrap().replicas.unwrap()).to_string();
+            let (name, annotations, resource_type) = keda.resolve(
+                &namespace,
+                name,
+                item.metadata.annotations,
+                Resources::Deployment,
+            );
             let pat = ScalingMachinery {
                 tobe_replicas: replicas,
                 original_replicas: original_count,
+                original_max_replicas: None,
+                original_policy: None,
                 name,
                 namespace,
-                annotations: item.metadata.annotations,
-                resource_type: Resources::Deployment,
+                annotations,
+                resource_type,
                 scale_state: Arc::clone(&scale_state),
             };
             if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
@@ -116,14 +177,23 @@ impl ResourceExtension for Api<Deployment> {
         client: Client,
     ) -> Result<(), Error> {
         let deploy_list = self.list(&Default::default()).await.unwrap();
+        let so_api: Api<ScaledObject> = Api::all(client.clone());
+        let keda = KedaTargets::deployments(&so_api).await?;
         for deploy in &deploy_list.items {
             debug!("parsing deployment resource {:?}", deploy.metadata.name);
+            let namespace = deploy.metadata.namespace.as_ref().unwrap().to_string();
+            let (name, annotations, resource_type) = keda.resolve(
+                &namespace,
+                deploy.metadata.name.as_ref().unwrap().to_string(),
+                deploy.metadata.annotations.to_owned(),
+                Resources::Deployment,
+            );
             let u = UpscaleMachinery {
                 replicas,
-                name: deploy.metadata.name.as_ref().unwrap().to_string(),
-                namespace: deploy.metadata.namespace.as_ref().unwrap().to_string(),
-                annotations: deploy.metadata.annotations.to_owned(),
-                resource_type: Resources::Deployment,
+                name,
+                namespace,
+                annotations,
+                resource_type,
             };
             u.upscale_machinery(client.clone()).await?
         }
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{client::Client, Api, CustomResource, Resource, ResourceExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::common::{list_or_empty, ScalingMachinery};
+
+// labels set by the flux controllers on the objects they apply
+const HELM_NAME_LABEL: &str = "helm.toolkit.fluxcd.io/name";
+const HELM_NAMESPACE_LABEL: &str = "helm.toolkit.fluxcd.io/namespace";
+const KUSTOMIZE_NAME_LABEL: &str = "kustomize.toolkit.fluxcd.io/name";
+const KUSTOMIZE_NAMESPACE_LABEL: &str = "kustomize.toolkit.fluxcd.io/namespace";
+
+/// Flux HelmRelease `helm.toolkit.fluxcd.io/v2`, only the suspend is typed
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "helm.toolkit.fluxcd.io",
+    version = "v2",
+    kind = "HelmRelease",
+    plural = "helmreleases",
+    namespaced,
+    schema = "disabled"
+)]
+pub struct HelmReleaseSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<bool>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+/// Flux Kustomization `kustomize.toolkit.fluxcd.io/v1`, only the suspend is typed
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "kustomize.toolkit.fluxcd.io",
+    version = "v1",
+    kind = "Kustomization",
+    plural = "kustomizations",
+    namespaced,
+    schema = "disabled"
+)]
+pub struct KustomizationSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<bool>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+impl JMSExpression for HelmRelease {}
+impl JMSExpression for Kustomization {}
+
+/// Kind, namespace and name of the flux object applying the workload, the HelmRelease wins
+/// over the Kustomization applying the HelmRelease
+pub(crate) fn flux_owner(
+    labels: Option<&BTreeMap<String, String>>,
+) -> Option<(Resources, String, String)> {
+    let labels = labels?;
+    [
+        (
+            Resources::HelmRelease,
+            HELM_NAMESPACE_LABEL,
+            HELM_NAME_LABEL,
+        ),
+        (
+            Resources::Kustomization,
+            KUSTOMIZE_NAMESPACE_LABEL,
+            KUSTOMIZE_NAME_LABEL,
+        ),
+    ]
+    .into_iter()
+    .find_map(|(kind, namespace, name)| {
+        Some((
+            kind,
+            labels.get(namespace)?.clone(),
+            labels.get(name)?.clone(),
+        ))
+    })
+}
+
+/// Suspends the flux object applying the workload for the downtime, so that it cannot re-apply the
+/// replicas, and resumes it at uptime, its result is reported as paired with the workload
+pub(crate) async fn suspend_owner(
+    c: Client,
+    labels: Option<&BTreeMap<String, String>>,
+    workload: &ScalingMachinery,
+    is_uptime: bool,
+) -> Result<Option<ScaledResources>, Error> {
+    let Some((kind, namespace, name)) = flux_owner(labels) else {
+        return Ok(None);
+    };
+    let annotations = match kind {
+        Resources::HelmRelease => Api::<HelmRelease>::namespaced(c.clone(), &namespace)
+            .get_opt(&name)
+            .await?
+            .map(|o| o.metadata.annotations),
+        _ => Api::<Kustomization>::namespaced(c.clone(), &namespace)
+            .get_opt(&name)
+            .await?
+            .map(|o| o.metadata.annotations),
+    };
+    let Some(annotations) = annotations else {
+        debug!("{} {}/{} not found, skipping", kind, namespace, name);
+        return Ok(None);
+    };
+    let pat = ScalingMachinery {
+        tobe_replicas: None,                // doesn't apply to flux
+        original_replicas: "0".to_string(), // doesn't apply to flux
+        original_max_replicas: None,
+        original_policy: None,
+        name,
+        namespace,
+        annotations,
+        resource_type: kind,
+        scale_state: Arc::clone(&workload.scale_state),
+    };
+    let scaled_res = pat.scaling_machinery(c, is_uptime).await?;
+    Ok(scaled_res.map(|r| ScaledResources {
+        paired_with: Some(format!("{}/{}", workload.resource_type, workload.name)),
+        ..r
+    }))
+}
+
+async fn suspend_items<K>(
+    items: Vec<K>,
+    kind: Resources,
+    c: Client,
+    is_uptime: bool,
+    scale_state: Arc<ScaleState>,
+) -> Result<Vec<ScaledResources>, Error>
+where
+    K: Resource + ResourceExt,
+{
+    let mut list_flux: Vec<ScaledResources> = vec![];
+    for item in items {
+        let pat = ScalingMachinery {
+            tobe_replicas: None,                // doesn't apply to flux
+            original_replicas: "0".to_string(), // doesn't apply to flux
+            original_max_replicas: None,
+            original_policy: None,
+            name: item.name_any(),
+            namespace: item.namespace().unwrap_or_default(),
+            annotations: item.meta().annotations.clone(),
+            resource_type: kind.clone(),
+            scale_state: Arc::clone(&scale_state),
+        };
+        if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+            list_flux.push(scaled_res);
+        }
+    }
+    Ok(list_flux)
+}
+
+async fn resume_items<K>(items: &[K], kind: Resources, client: Client) -> Result<(), Error>
+where
+    K: Resource + ResourceExt,
+{
+    for item in items {
+        debug!("parsing {} resource {:?}", kind, item.meta().name);
+        let u = UpscaleMachinery {
+            replicas: None,
+            name: item.name_any(),
+            namespace: item.namespace().unwrap_or_default(),
+            annotations: item.meta().annotations.clone(),
+            resource_type: kind.clone(),
+        };
+        u.upscale_machinery(client.clone()).await?
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Flux<'a> {
+    pub(crate) kind: Resources,
+    pub(crate) selection: &'a Selection,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Flux<'a> {
+    pub fn new(kind: Resources, selection: &'a Selection, is_uptime: bool) -> Self {
+        Flux {
+            kind,
+            selection,
+            is_uptime,
+        }
+    }
+}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for Flux<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        match self.kind {
+            Resources::HelmRelease => {
+                let list = list_or_empty(
+                    &self.selection.api::<HelmRelease>(c.clone()),
+                    &self.selection.list_params(),
+                    "flux objects",
+                )
+                .await?;
+                let mut items = vec![];
+                for item in list.items {
+                    if self.selection.matches(&item).await? {
+                        items.push(item);
+                    }
+                }
+                suspend_items(items, self.kind.clone(), c, self.is_uptime, scale_state).await
+            }
+            _ => {
+                let list = list_or_empty(
+                    &self.selection.api::<Kustomization>(c.clone()),
+                    &self.selection.list_params(),
+                    "flux objects",
+                )
+                .await?;
+                let mut items = vec![];
+                for item in list.items {
+                    if self.selection.matches(&item).await? {
+                        items.push(item);
+                    }
+                }
+                suspend_items(items, self.kind.clone(), c, self.is_uptime, scale_state).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<HelmRelease> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching helmrelease: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "flux objects").await?;
+        suspend_items(
+            list.items,
+            Resources::HelmRelease,
+            c,
+            is_uptime,
+            scale_state,
+        )
+        .await
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let list = list_or_empty(self, &ListParams::default(), "flux objects").await?;
+        resume_items(&list.items, Resources::HelmRelease, client).await
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<Kustomization> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching kustomization: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "flux objects").await?;
+        suspend_items(
+            list.items,
+            Resources::Kustomization,
+            c,
+            is_uptime,
+            scale_state,
+        )
+        .await
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let list = list_or_empty(self, &ListParams::default(), "flux objects").await?;
+        resume_items(&list.items, Resources::Kustomization, client).await
+    }
+}
+
+#[test]
+fn validate_flux_owner() {
+    let labels = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+    let kustomized = labels(&[
+        (KUSTOMIZE_NAME_LABEL, "apps"),
+        (KUSTOMIZE_NAMESPACE_LABEL, "flux-system"),
+    ]);
+    assert_eq!(
+        flux_owner(Some(&kustomized)),
+        Some((
+            Resources::Kustomization,
+            "flux-system".to_owned(),
+            "apps".to_owned()
+        ))
+    );
+    let released = labels(&[
+        (KUSTOMIZE_NAME_LABEL, "apps"),
+        (KUSTOMIZE_NAMESPACE_LABEL, "flux-system"),
+        (HELM_NAME_LABEL, "podinfo"),
+        (HELM_NAMESPACE_LABEL, "kuber"),
+    ]);
+    assert_eq!(
+        flux_owner(Some(&released)),
+        Some((
+            Resources::HelmRelease,
+            "kuber".to_owned(),
+            "podinfo".to_owned()
+        ))
+    );
+    // both the name and namespace labels are required
+    assert_eq!(
+        flux_owner(Some(&labels(&[(HELM_NAME_LABEL, "podinfo")]))),
+        None
+    );
+    assert_eq!(flux_owner(None), None);
+}
@@ -1,21 +1,135 @@
 use std::sync::Arc;
 
 use crate::controller::common::UpscaleMachinery;
-use crate::downscaler::{JMSExpression, Res, ResourceExtension, Resources, ScaledResources};
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
 use crate::error::Error;
 use crate::ScaleState;
 use async_trait::async_trait;
-use k8s_openapi::api::autoscaling::v1::HorizontalPodAutoscaler;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use kube::api::{Patch, PatchParams};
 use kube::{client::Client, Api};
 use log::{debug, info};
-use serde_json::Value;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 
 use super::common::ScalingMachinery;
 
-#[derive(Debug, PartialEq, Eq, Default)]
+/// maxReplicas of the hpa before it was downscaled, minReplicas is kept in `kubesaver.com/original_count`
+pub const ORIGINAL_MAX_REPLICAS_ANNOTATION: &str = "kubesaver.com/original_max_replicas";
+
+// min and max replicas of the hpa, min defaults to 1 as in kubernetes
+fn hpa_replicas(hpa: &HorizontalPodAutoscaler) -> (i32, i32) {
+    hpa.spec
+        .as_ref()
+        .map_or((1, 1), |s| (s.min_replicas.unwrap_or(1), s.max_replicas))
+}
+
+/// Original maxReplicas saved in the annotations when the hpa was downscaled
+pub(crate) fn original_max_replicas(annotations: Option<&BTreeMap<String, String>>) -> Option<i32> {
+    annotations?
+        .get(ORIGINAL_MAX_REPLICAS_ANNOTATION)?
+        .parse()
+        .ok()
+}
+
+/// Value of the saved maxReplicas annotation, set at downtime when the hpa had one and removed
+/// once it is restored at uptime
+pub(crate) fn max_replicas_annotation(
+    original_max_replicas: Option<i32>,
+    is_downscale: bool,
+) -> Option<Value> {
+    match is_downscale {
+        true => original_max_replicas.map(|max_replicas| json!(max_replicas.to_string())),
+        false => Some(Value::Null),
+    }
+}
+
+/// Spec patch of the hpa, at downtime maxReplicas is held at minReplicas so that the hpa cannot
+/// scale up on load, at uptime the original maxReplicas is restored if it was saved
+pub(crate) fn hpa_replicas_patch(min_replicas: i32, max_replicas: Option<i32>) -> Value {
+    let mut spec = Map::new();
+    spec.insert("minReplicas".to_string(), json!(min_replicas)); // minReplicas should >=1
+    if let Some(max_replicas) = max_replicas {
+        // maxReplicas cannot be lower than minReplicas
+        spec.insert(
+            "maxReplicas".to_string(),
+            json!(max_replicas.max(min_replicas)),
+        );
+    }
+    Value::Object(spec)
+}
+
+/// Hpas by the namespace, kind and name of the workload they scale
+#[derive(Debug, Default)]
+pub(crate) struct HpaTargets(BTreeMap<(String, String, String), HorizontalPodAutoscaler>);
+
+impl HpaTargets {
+    pub(crate) async fn list(api: &Api<HorizontalPodAutoscaler>) -> Result<Self, Error> {
+        Ok(Self::from_hpas(api.list(&Default::default()).await?.items))
+    }
+
+    fn from_hpas(items: Vec<HorizontalPodAutoscaler>) -> Self {
+        HpaTargets(
+            items
+                .into_iter()
+                .filter_map(|hpa| {
+                    let target = hpa.spec.as_ref()?.scale_target_ref.clone();
+                    let namespace = hpa.metadata.namespace.clone()?;
+                    Some(((namespace, target.kind, target.name), hpa))
+                })
+                .collect(),
+        )
+    }
+
+    fn get(
+        &self,
+        namespace: &str,
+        kind: &Resources,
+        name: &str,
+    ) -> Option<&HorizontalPodAutoscaler> {
+        self.0
+            .get(&(namespace.to_owned(), kind.to_string(), name.to_owned()))
+    }
+
+    /// An hpa targeting the workload would scale it back up, so the hpa is scaled before the
+    /// workload and held at the replicas of the rule, its result is reported as paired with the workload
+    pub(crate) async fn scale_paired(
+        &self,
+        c: Client,
+        workload: &ScalingMachinery,
+        is_uptime: bool,
+    ) -> Result<Option<ScaledResources>, Error> {
+        let (namespace, kind, name) =
+            (&workload.namespace, &workload.resource_type, &workload.name);
+        let Some(hpa) = self.get(namespace, kind, name) else {
+            return Ok(None);
+        };
+        let (original_count, original_max) = hpa_replicas(hpa);
+        let pat = ScalingMachinery {
+            // hpa minReplicas cannot be 0
+            tobe_replicas: Some(workload.tobe_replicas.unwrap_or(1).max(1)),
+            original_replicas: original_count.to_string(),
+            original_max_replicas: Some(original_max),
+            original_policy: None,
+            name: hpa.metadata.name.clone().unwrap(),
+            namespace: namespace.to_owned(),
+            annotations: hpa.metadata.annotations.clone(),
+            resource_type: Resources::Hpa,
+            scale_state: Arc::clone(&workload.scale_state),
+        };
+        let scaled_res = pat.scaling_machinery(c, is_uptime).await?;
+        Ok(scaled_res.map(|r| ScaledResources {
+            paired_with: Some(format!("{}/{}", kind, name)),
+            ..r
+        }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct Hpa<'a> {
-    pub(crate) expression: &'a str,
+    pub(crate) selection: &'a Selection,
     pub(crate) replicas: Option<i32>,
     pub(crate) is_uptime: bool,
 }
@@ -24,9 +138,9 @@ impl JMSExpression for HorizontalPodAutoscaler {}
 
 #[allow(clippy::needless_lifetimes)]
 impl<'a> Hpa<'a> {
-    pub fn new(expression: &'a str, replicas: Option<i32>, is_uptime: bool) -> Self {
+    pub fn new(selection: &'a Selection, replicas: Option<i32>, is_uptime: bool) -> Self {
         Hpa {
-            expression,
+            selection,
             replicas,
             is_uptime,
         }
@@ -41,13 +155,13 @@ impl<'a> Res for Hpa<'a> {
         c: Client,
         scale_state: Arc<ScaleState>,
     ) -> Result<Vec<ScaledResources>, Error> {
-        let api: Api<HorizontalPodAutoscaler> = Api::all(c.clone());
-        let list = api.list(&Default::default()).await.unwrap();
+        let api: Api<HorizontalPodAutoscaler> = self.selection.api(c.clone());
+        let list = api.list(&self.selection.list_params()).await.unwrap();
         let mut list_hpa: Vec<ScaledResources> = vec![];
         // TODO: Multiple threads
         for item in list.items {
-            let result = item.parse(self.expression).await?;
-            let original_count = (item.spec.unwrap().min_replicas.unwrap()).to_string();
+            let result = self.selection.matches(&item).await?;
+            let (original_count, original_max) = hpa_replicas(&item);
             if result {
                 // if the replicas is set to 0 on the input resource type = 'Namespace', make sure Hpa cannot be set to 0
                 // Hence always set it to 1 and the dependent Deployment will be set to 0
@@ -62,7 +176,9 @@ impl<'a> Res for Hpa<'a> {
                 };
                 let pat = ScalingMachinery {
                     tobe_replicas: replicas,
-                    original_replicas: original_count,
+                    original_replicas: original_count.to_string(),
+                    original_max_replicas: Some(original_max),
+                    original_policy: None,
                     name,
                     namespace,
                     annotations: item.metadata.annotations,
@@ -96,13 +212,13 @@ impl ResourceExtension for Api<HorizontalPodAutoscaler> {
         let list = self.list(&Default::default()).await?;
         let mut list_hpa: Vec<ScaledResources> = vec![];
         for item in list.items {
+            let (original_count, original_max) = hpa_replicas(&item);
             let name = item.metadata.name.unwrap();
             let namespace = item.metadata.namespace.unwrap();
             debug!(
                 "Parsing hpa {} since its in namespace {:?}",
                 name, namespace
             );
-            let original_count = (item.spec.unwrap().min_replicas.unwrap()).to_string();
             // if the replicas is set to 0 on the input resource type = 'Namespace', make sure Hpa cannot be set to 0
             // Hence always set it to 1 and the dependent Deployment will be set to 0
             let replicas = if let Some(0) = replicas {
@@ -113,7 +229,9 @@ impl ResourceExtension for Api<HorizontalPodAutoscaler> {
 
             let pat = ScalingMachinery {
                 tobe_replicas: replicas,
-                original_replicas: original_count,
+                original_replicas: original_count.to_string(),
+                original_max_replicas: Some(original_max),
+                original_policy: None,
                 name,
                 namespace,
                 annotations: item.metadata.annotations,
@@ -153,3 +271,81 @@ impl ResourceExtension for Api<HorizontalPodAutoscaler> {
         Ok(())
     }
 }
+
+#[test]
+fn validate_hpa_replicas_patch() {
+    let hpa: HorizontalPodAutoscaler = serde_json::from_value(json!({
+        "metadata": { "name": "web", "namespace": "kuber" },
+        "spec": {
+            "scaleTargetRef": { "apiVersion": "apps/v1", "kind": "Deployment", "name": "web" },
+            "maxReplicas": 20,
+            "metrics": [{ "type": "Resource", "resource": { "name": "cpu", "target": { "type": "Utilization", "averageUtilization": 60 } } }]
+        }
+    }))
+    .unwrap();
+    assert_eq!(hpa_replicas(&hpa), (1, 20));
+    // downtime holds maxReplicas at minReplicas
+    assert_eq!(
+        hpa_replicas_patch(1, Some(1)),
+        json!({ "minReplicas": 1, "maxReplicas": 1 })
+    );
+    // uptime restores the saved maxReplicas, never lower than minReplicas
+    let annotations = BTreeMap::from([(
+        ORIGINAL_MAX_REPLICAS_ANNOTATION.to_string(),
+        "20".to_string(),
+    )]);
+    let max = original_max_replicas(Some(&annotations));
+    assert_eq!(max, Some(20));
+    assert_eq!(
+        hpa_replicas_patch(3, max),
+        json!({ "minReplicas": 3, "maxReplicas": 20 })
+    );
+    assert_eq!(
+        hpa_replicas_patch(30, max),
+        json!({ "minReplicas": 30, "maxReplicas": 30 })
+    );
+    // the annotation is saved at downtime and removed once maxReplicas is restored
+    assert_eq!(max_replicas_annotation(Some(20), true), Some(json!("20")));
+    assert_eq!(max_replicas_annotation(None, true), None);
+    assert_eq!(max_replicas_annotation(Some(20), false), Some(Value::Null));
+    // hpas downscaled before the maxReplicas was saved only restore minReplicas
+    assert_eq!(
+        hpa_replicas_patch(3, original_max_replicas(None)),
+        json!({ "minReplicas": 3 })
+    );
+}
+
+#[test]
+fn validate_hpa_targets() {
+    let hpa = |name: &str, kind: &str, target: &str| -> HorizontalPodAutoscaler {
+        serde_json::from_value(json!({
+            "metadata": { "name": name, "namespace": "kuber" },
+            "spec": {
+                "scaleTargetRef": { "apiVersion": "apps/v1", "kind": kind, "name": target },
+                "maxReplicas": 5
+            }
+        }))
+        .unwrap()
+    };
+    let targets = HpaTargets::from_hpas(vec![
+        hpa("web-hpa", "Deployment", "web"),
+        hpa("db-hpa", "StatefulSet", "db"),
+    ]);
+    let name = |hpa: Option<&HorizontalPodAutoscaler>| hpa.and_then(|h| h.metadata.name.clone());
+    assert_eq!(
+        name(targets.get("kuber", &Resources::Deployment, "web")),
+        Some("web-hpa".to_owned())
+    );
+    assert_eq!(
+        name(targets.get("kuber", &Resources::StatefulSet, "db")),
+        Some("db-hpa".to_owned())
+    );
+    assert_eq!(
+        name(targets.get("kuber", &Resources::StatefulSet, "web")),
+        None
+    );
+    assert_eq!(
+        name(targets.get("other", &Resources::Deployment, "web")),
+        None
+    );
+}
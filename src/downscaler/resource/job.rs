@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    ActiveJobs, JMSExpression, Res, ResourceExtension, Resources, ScaleAction, ScaledResources,
+    Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use k8s_openapi::api::batch::v1::Job;
+use kube::api::{DeleteParams, Patch, PatchParams};
+use kube::{client::Client, Api};
+use log::{debug, info};
+use serde_json::Value;
+
+use super::common::ScalingMachinery;
+
+// completed and failed jobs have nothing left to suspend or delete
+fn is_finished(job: &Job) -> bool {
+    job.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_ref())
+        .is_some_and(|conditions| {
+            conditions
+                .iter()
+                .any(|c| (c.type_ == "Complete" || c.type_ == "Failed") && c.status == "True")
+        })
+}
+
+fn is_owned_by_cronjob(job: &Job, cronjob: &str) -> bool {
+    job.metadata
+        .owner_references
+        .as_ref()
+        .is_some_and(|owners| {
+            owners
+                .iter()
+                .any(|o| o.kind == "CronJob" && o.name == cronjob)
+        })
+}
+
+// jobs of the cronjob, or every job of the namespace without cronjob
+fn is_active_job_of(job: &Job, cronjob: Option<&str>) -> bool {
+    cronjob.is_none_or(|cronjob| is_owned_by_cronjob(job, cronjob))
+}
+
+/// Suspends or deletes the running jobs of the cronjob at downtime, the suspended jobs are resumed at uptime
+pub(crate) async fn scale_cronjob_jobs(
+    c: Client,
+    namespace: &str,
+    cronjob: &str,
+    active_jobs: ActiveJobs,
+    is_uptime: bool,
+    scale_state: Arc<ScaleState>,
+) -> Result<Vec<ScaledResources>, Error> {
+    scale_active_jobs(
+        c,
+        namespace,
+        Some(cronjob),
+        active_jobs,
+        is_uptime,
+        scale_state,
+    )
+    .await
+}
+
+/// Suspends or deletes the running jobs of the namespace at downtime, standalone or started by a
+/// cronjob, the suspended jobs are resumed at uptime
+pub(crate) async fn scale_namespace_jobs(
+    c: Client,
+    namespace: &str,
+    active_jobs: ActiveJobs,
+    is_uptime: bool,
+    scale_state: Arc<ScaleState>,
+) -> Result<Vec<ScaledResources>, Error> {
+    scale_active_jobs(c, namespace, None, active_jobs, is_uptime, scale_state).await
+}
+
+async fn scale_active_jobs(
+    c: Client,
+    namespace: &str,
+    cronjob: Option<&str>,
+    active_jobs: ActiveJobs,
+    is_uptime: bool,
+    scale_state: Arc<ScaleState>,
+) -> Result<Vec<ScaledResources>, Error> {
+    let api: Api<Job> = Api::namespaced(c.clone(), namespace);
+    let list = api.list(&Default::default()).await?;
+    let mut list_job: Vec<ScaledResources> = vec![];
+    for item in list.items {
+        if !is_active_job_of(&item, cronjob) {
+            continue;
+        }
+        let is_running = !is_finished(&item);
+        let name = item.metadata.name.unwrap();
+        match active_jobs {
+            ActiveJobs::Delete if !is_uptime && is_running => {
+                info!("deleting job {} in namespace {}", name, namespace);
+                api.delete(&name, &DeleteParams::background()).await?;
+                list_job.push(ScaledResources {
+                    name,
+                    namespace: namespace.to_owned(),
+                    kind: Resources::Job,
+                    action: ScaleAction::Delete,
+                    paired_with: None,
+                });
+            }
+            ActiveJobs::Suspend if is_uptime || is_running => {
+                let pat = ScalingMachinery {
+                    tobe_replicas: None,                // doesn't apply to job
+                    original_replicas: "0".to_string(), // doesn't apply to job
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name,
+                    namespace: namespace.to_owned(),
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::Job,
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                    list_job.push(scaled_res);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(list_job)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BatchJob<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> BatchJob<'a> {
+    pub fn new(selection: &'a Selection, is_uptime: bool) -> Self {
+        BatchJob {
+            selection,
+            is_uptime,
+        }
+    }
+}
+
+impl JMSExpression for Job {}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for BatchJob<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<Job> = self.selection.api(c.clone());
+        let list = api.list(&self.selection.list_params()).await?;
+        let mut list_job: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            // finished jobs cannot be suspended, but the suspended ones are resumed at uptime
+            if !self.is_uptime && is_finished(&item) {
+                continue;
+            }
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = ScalingMachinery {
+                    tobe_replicas: None,                // doesn't apply to job
+                    original_replicas: "0".to_string(), // doesn't apply to job
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name: item.metadata.name.unwrap(),
+                    namespace: item.metadata.namespace.unwrap(),
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::Job,
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_job.push(scaled_res);
+                }
+            }
+        }
+        Ok(list_job)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<Job> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching job resource {:?}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = self.list(&Default::default()).await?;
+        let mut list_job: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            if !is_uptime && is_finished(&item) {
+                continue;
+            }
+            let name = item.metadata.name.unwrap();
+            let namespace = item.metadata.namespace.unwrap();
+            debug!(
+                "Parsing job {} since its in namespace {:?}",
+                name, namespace
+            );
+            let pat = ScalingMachinery {
+                tobe_replicas: None,                // doesn't apply to job
+                original_replicas: "0".to_string(), // doesn't apply to job
+                original_max_replicas: None,
+                original_policy: None,
+                name,
+                namespace,
+                annotations: item.metadata.annotations,
+                resource_type: Resources::Job,
+                scale_state: Arc::clone(&scale_state),
+            };
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_job.push(scaled_res);
+            }
+        }
+        Ok(list_job)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let job_list = self.list(&Default::default()).await?;
+        for job in &job_list.items {
+            debug!("parsing job resource {:?}", job.metadata.name);
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: job.metadata.name.as_ref().unwrap().to_string(),
+                namespace: job.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: job.metadata.annotations.to_owned(),
+                resource_type: Resources::Job,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_cronjob_children() {
+    let job = |conditions: serde_json::Value| -> Job {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "name": "backup-28765440",
+                "namespace": "kuber",
+                "ownerReferences": [{
+                    "apiVersion": "batch/v1", "kind": "CronJob", "name": "backup", "uid": "1"
+                }]
+            },
+            "spec": { "template": { "spec": { "containers": [{ "name": "backup" }] } } },
+            "status": { "conditions": conditions }
+        }))
+        .unwrap()
+    };
+    let running = job(serde_json::json!([]));
+    assert!(is_owned_by_cronjob(&running, "backup"));
+    assert!(!is_owned_by_cronjob(&running, "restore"));
+    // a namespace rule applies active_jobs to every job of the namespace
+    assert!(is_active_job_of(&running, Some("backup")));
+    assert!(!is_active_job_of(&running, Some("restore")));
+    assert!(is_active_job_of(&running, None));
+    assert!(!is_finished(&running));
+    let suspended = job(serde_json::json!([{ "type": "Suspended", "status": "True" }]));
+    assert!(!is_finished(&suspended));
+    let complete = job(serde_json::json!([{ "type": "Complete", "status": "True" }]));
+    assert!(is_finished(&complete));
+    let failed = job(serde_json::json!([{ "type": "Failed", "status": "True" }]));
+    assert!(is_finished(&failed));
+}
@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{client::Client, Api, CustomResource, ResourceExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::common::{list_or_empty, ScalingMachinery};
+
+/// KEDA keeps the target at these replicas and stops scaling it while the annotation is set
+pub const PAUSED_REPLICAS_ANNOTATION: &str = "autoscaling.keda.sh/paused-replicas";
+
+/// KEDA ScaledObject `keda.sh/v1alpha1`, only the target and the minimum replicas are typed
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "keda.sh",
+    version = "v1alpha1",
+    kind = "ScaledObject",
+    plural = "scaledobjects",
+    namespaced,
+    schema = "disabled"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaledObjectSpec {
+    pub scale_target_ref: ScaleTargetRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_replica_count: Option<i32>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaleTargetRef {
+    pub name: String,
+    // keda defaults the target to a Deployment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+}
+
+impl ScaledObject {
+    fn targets_deployment(&self) -> bool {
+        self.spec
+            .scale_target_ref
+            .kind
+            .as_deref()
+            .is_none_or(|k| k == "Deployment")
+    }
+}
+
+/// Scaled objects by the namespace and name of the Deployment they scale
+#[derive(Debug, Default)]
+pub(crate) struct KedaTargets(BTreeMap<(String, String), ScaledObject>);
+
+impl KedaTargets {
+    pub(crate) async fn deployments(api: &Api<ScaledObject>) -> Result<Self, Error> {
+        Ok(Self::from_scaled_objects(
+            list_or_empty(api, &ListParams::default(), "KEDA scaled objects")
+                .await?
+                .items,
+        ))
+    }
+
+    fn from_scaled_objects(items: Vec<ScaledObject>) -> Self {
+        KedaTargets(
+            items
+                .into_iter()
+                .filter(|so| so.targets_deployment())
+                .map(|so| {
+                    let target = (
+                        so.namespace().unwrap_or_default(),
+                        so.spec.scale_target_ref.name.clone(),
+                    );
+                    (target, so)
+                })
+                .collect(),
+        )
+    }
+
+    /// KEDA undoes the replicas of the Deployments it scales, so those are paused through
+    /// their scaled object instead, returns the name, annotations and type to patch
+    pub(crate) fn resolve(
+        &self,
+        namespace: &str,
+        name: String,
+        annotations: Option<BTreeMap<String, String>>,
+        resource_type: Resources,
+    ) -> (String, Option<BTreeMap<String, String>>, Resources) {
+        match self.0.get(&(namespace.to_owned(), name.clone())) {
+            Some(so) => {
+                debug!(
+                    "{} {} is scaled by KEDA, pausing the scaled object {}",
+                    resource_type,
+                    so.spec.scale_target_ref.name,
+                    so.name_any()
+                );
+                (
+                    so.name_any(),
+                    so.metadata.annotations.clone(),
+                    Resources::ScaledObject,
+                )
+            }
+            None => (name, annotations, resource_type),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct KedaScaledObject<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) replicas: Option<i32>,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> KedaScaledObject<'a> {
+    pub fn new(selection: &'a Selection, replicas: Option<i32>, is_uptime: bool) -> Self {
+        KedaScaledObject {
+            selection,
+            replicas,
+            is_uptime,
+        }
+    }
+}
+
+impl JMSExpression for ScaledObject {}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for KedaScaledObject<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<ScaledObject> = self.selection.api(c.clone());
+        let list =
+            list_or_empty(&api, &self.selection.list_params(), "KEDA scaled objects").await?;
+        let mut list_so: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = ScalingMachinery {
+                    tobe_replicas: self.replicas,
+                    original_replicas: item.spec.min_replica_count.unwrap_or(0).to_string(),
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name: item.metadata.name.unwrap(),
+                    namespace: item.metadata.namespace.unwrap(),
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::ScaledObject,
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_so.push(scaled_res);
+                };
+            }
+        }
+        Ok(list_so)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<ScaledObject> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching scaled object: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "KEDA scaled objects").await?;
+        let mut list_so: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let name = item.metadata.name.unwrap();
+            let namespace = item.metadata.namespace.unwrap();
+            debug!(
+                "Parsing scaled object {} since its in namespace {:?}",
+                name, namespace
+            );
+            let pat = ScalingMachinery {
+                tobe_replicas: replicas,
+                original_replicas: item.spec.min_replica_count.unwrap_or(0).to_string(),
+                original_max_replicas: None,
+                original_policy: None,
+                name,
+                namespace,
+                annotations: item.metadata.annotations,
+                resource_type: Resources::ScaledObject,
+                scale_state: Arc::clone(&scale_state),
+            };
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_so.push(scaled_res);
+            };
+        }
+        Ok(list_so)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let so_list = list_or_empty(self, &ListParams::default(), "KEDA scaled objects").await?;
+        for so in &so_list.items {
+            debug!("parsing scaled object resource {:?}", so.metadata.name);
+            let u = UpscaleMachinery {
+                replicas,
+                name: so.metadata.name.as_ref().unwrap().to_string(),
+                namespace: so.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: so.metadata.annotations.to_owned(),
+                resource_type: Resources::ScaledObject,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_keda_targets_resolve() {
+    let so: ScaledObject = serde_json::from_value(serde_json::json!({
+        "apiVersion": "keda.sh/v1alpha1",
+        "kind": "ScaledObject",
+        "metadata": { "name": "web-so", "namespace": "kuber", "annotations": { "app": "web" } },
+        "spec": {
+            "scaleTargetRef": { "name": "web" },
+            "minReplicaCount": 2,
+            "triggers": [{ "type": "cpu", "metadata": { "value": "60" } }]
+        }
+    }))
+    .unwrap();
+    let mut job = so.clone();
+    job.metadata.name = Some("job-so".to_owned());
+    job.spec.scale_target_ref = ScaleTargetRef {
+        name: "job".to_owned(),
+        kind: Some("StatefulSet".to_owned()),
+        api_version: Some("apps/v1".to_owned()),
+    };
+    assert!(so.spec.rest.contains_key("triggers"));
+    let targets = KedaTargets::from_scaled_objects(vec![so, job]);
+    let (name, annotations, resource_type) =
+        targets.resolve("kuber", "web".to_owned(), None, Resources::Deployment);
+    assert_eq!(name, "web-so");
+    assert_eq!(annotations.unwrap().get("app").unwrap(), "web");
+    assert_eq!(resource_type, Resources::ScaledObject);
+    // same name in another namespace is not scaled by keda
+    assert_eq!(
+        targets.resolve("other", "web".to_owned(), None, Resources::Deployment),
+        ("web".to_owned(), None, Resources::Deployment)
+    );
+    // only the scaled objects targeting a Deployment are paired
+    assert_eq!(
+        targets
+            .resolve("kuber", "job".to_owned(), None, Resources::Deployment)
+            .2,
+        Resources::Deployment
+    );
+}
@@ -1,6 +1,14 @@
+pub mod argocd;
 pub mod common;
 pub mod cronjob;
+pub mod daemonset;
 pub mod deployment;
+pub mod flux;
 pub mod hpa;
+pub mod job;
+pub mod keda;
 pub mod namespace;
+pub mod rollout;
+pub mod scale;
 pub mod statefulset;
+pub mod vpa;
@@ -1,29 +1,44 @@
 use std::sync::Arc;
 
-use crate::downscaler::{JMSExpression, Res, ResourceExtension, ScaledResources};
+use crate::downscaler::resource::argocd::ArgoApps;
+use crate::downscaler::resource::job::scale_namespace_jobs;
+use crate::downscaler::resource::rollout::Rollout;
+use crate::downscaler::{
+    ActiveJobs, JMSExpression, Res, ResourceExtension, ScaledResources, Selection,
+};
 use crate::error::Error;
 use crate::ScaleState;
 use async_trait::async_trait;
-use k8s_openapi::api::autoscaling::v1::HorizontalPodAutoscaler;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use k8s_openapi::api::{
     apps::v1::Deployment, apps::v1::StatefulSet, batch::v1::CronJob, core::v1::Namespace,
 };
 use kube::{client::Client, Api};
 use log::debug;
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Nspace<'a> {
-    pub(crate) expression: &'a str,
+    pub(crate) selection: &'a Selection,
     pub(crate) replicas: Option<i32>,
     pub(crate) is_uptime: bool,
+    pub(crate) active_jobs: Option<ActiveJobs>,
+    pub(crate) pause_argocd: bool,
 }
 
 #[allow(clippy::needless_lifetimes)]
 impl<'a> Nspace<'a> {
-    pub fn new(expression: &'a str, replicas: Option<i32>, is_uptime: bool) -> Self {
+    pub fn new(
+        selection: &'a Selection,
+        replicas: Option<i32>,
+        is_uptime: bool,
+        active_jobs: Option<ActiveJobs>,
+        pause_argocd: Option<bool>,
+    ) -> Self {
         Nspace {
-            expression,
+            selection,
             replicas,
             is_uptime,
+            active_jobs,
+            pause_argocd: pause_argocd.unwrap_or_default(),
         }
     }
 }
@@ -39,12 +54,17 @@ impl<'a> Res for Nspace<'a> {
         s: Arc<ScaleState>,
     ) -> Result<Vec<ScaledResources>, Error> {
         let api: Api<Namespace> = Api::all(c.clone());
-        let namespaces = api.list(&Default::default()).await.unwrap();
+        let namespaces = api.list(&self.selection.list_params()).await.unwrap();
         let mut list_namespace: Vec<Vec<ScaledResources>> = vec![];
+        // the applications are only paused when the rule opts in with pause_argocd
+        let mut argo = match self.pause_argocd {
+            true => ArgoApps::list(&Api::all(c.clone())).await?,
+            false => ArgoApps::default(),
+        };
 
         // TODO: Multiple threads
         for ns in namespaces.items {
-            let result = ns.parse(self.expression).await?;
+            let result = self.selection.matches(&ns).await?;
             if result {
                 let namespace_name = ns.metadata.name.unwrap();
                 debug!(
@@ -69,6 +89,18 @@ impl<'a> Res for Nspace<'a> {
                         )
                         .await?,
                 );
+                // self-heal would restore the replicas of the deployments and statefulsets
+                if self.pause_argocd {
+                    list_namespace.push(
+                        argo.pause_namespace_owners(
+                            c.clone(),
+                            &namespace_name,
+                            self.is_uptime,
+                            Arc::clone(&s),
+                        )
+                        .await?,
+                    );
+                }
                 debug!(
                     "Checking if any Deployment resources in namespace {}",
                     namespace_name
@@ -114,6 +146,38 @@ impl<'a> Res for Nspace<'a> {
                         )
                         .await?,
                 );
+                // the running jobs of the namespace, standalone or started by its cronjobs
+                if let Some(active_jobs) = self.active_jobs {
+                    debug!(
+                        "Checking if any running Job resources in namespace {}",
+                        namespace_name
+                    );
+                    list_namespace.push(
+                        scale_namespace_jobs(
+                            c.clone(),
+                            &namespace_name,
+                            active_jobs,
+                            self.is_uptime,
+                            Arc::clone(&s),
+                        )
+                        .await?,
+                    );
+                }
+                debug!(
+                    "Checking if any Rollout resources in namespace {}",
+                    namespace_name
+                );
+                let ro_api: Api<Rollout> = Api::namespaced(c.clone(), &namespace_name);
+                list_namespace.push(
+                    ro_api
+                        .processor_scale_ns_resource_items(
+                            self.replicas,
+                            c.clone(),
+                            self.is_uptime,
+                            Arc::clone(&s),
+                        )
+                        .await?,
+                );
             }
         }
         Ok(list_namespace.into_iter().flatten().collect())
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{client::Client, Api, CustomResource};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::common::{list_or_empty, ScalingMachinery};
+
+/// Argo Rollout `argoproj.io/v1alpha1`, only the replicas are typed and the rest of the spec is kept as is
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "argoproj.io",
+    version = "v1alpha1",
+    kind = "Rollout",
+    plural = "rollouts",
+    namespaced,
+    schema = "disabled"
+)]
+pub struct RolloutSpec {
+    // argo defaults the replicas to 1 when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<i32>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+impl Rollout {
+    fn replicas(&self) -> i32 {
+        self.spec.replicas.unwrap_or(1)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArgoRollout<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) replicas: Option<i32>,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> ArgoRollout<'a> {
+    pub fn new(selection: &'a Selection, replicas: Option<i32>, is_uptime: bool) -> Self {
+        ArgoRollout {
+            selection,
+            replicas,
+            is_uptime,
+        }
+    }
+}
+
+impl JMSExpression for Rollout {}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for ArgoRollout<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<Rollout> = self.selection.api(c.clone());
+        let list = list_or_empty(&api, &self.selection.list_params(), "Argo Rollouts").await?;
+        let mut list_rollout: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = ScalingMachinery {
+                    tobe_replicas: self.replicas,
+                    original_replicas: item.replicas().to_string(),
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name: item.metadata.name.unwrap(),
+                    namespace: item.metadata.namespace.unwrap(),
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::Rollout,
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_rollout.push(scaled_res);
+                };
+            }
+        }
+        Ok(list_rollout)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<Rollout> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching rollout: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "Argo Rollouts").await?;
+        let mut list_rollout: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let original_count = item.replicas().to_string();
+            let name = item.metadata.name.unwrap();
+            let namespace = item.metadata.namespace.unwrap();
+            debug!(
+                "Parsing rollout {} since its in namespace {:?}",
+                name, namespace
+            );
+            let pat = ScalingMachinery {
+                tobe_replicas: replicas,
+                original_replicas: original_count,
+                original_max_replicas: None,
+                original_policy: None,
+                name,
+                namespace,
+                annotations: item.metadata.annotations,
+                resource_type: Resources::Rollout,
+                scale_state: Arc::clone(&scale_state),
+            };
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_rollout.push(scaled_res);
+            };
+        }
+        Ok(list_rollout)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let rollout_list = list_or_empty(self, &ListParams::default(), "Argo Rollouts").await?;
+        for rollout in &rollout_list.items {
+            debug!("parsing rollout resource {:?}", rollout.metadata.name);
+            let u = UpscaleMachinery {
+                replicas,
+                name: rollout.metadata.name.as_ref().unwrap().to_string(),
+                namespace: rollout.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: rollout.metadata.annotations.to_owned(),
+                resource_type: Resources::Rollout,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn validate_rollout_keeps_the_spec() {
+    let rollout: Rollout = serde_json::from_value(serde_json::json!({
+        "apiVersion": "argoproj.io/v1alpha1",
+        "kind": "Rollout",
+        "metadata": { "name": "rollout", "namespace": "kuber", "labels": { "app": "rollout" } },
+        "spec": {
+            "strategy": { "canary": { "steps": [{ "setWeight": 20 }] } },
+            "selector": { "matchLabels": { "app": "rollout" } }
+        }
+    }))
+    .unwrap();
+    assert_eq!(rollout.replicas(), 1);
+    assert!(rollout.spec.rest.contains_key("strategy"));
+    assert!(rollout
+        .parse(
+            &crate::downscaler::JmesPath::compile(
+                "rule id rollout",
+                "metadata.labels.app == 'rollout' && spec.selector.matchLabels.app == 'rollout'"
+            )
+            .unwrap()
+        )
+        .await
+        .unwrap());
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use kube::api::{ApiResource, DynamicObject, ListParams, Patch, PatchParams};
+use kube::discovery::{pinned_kind, ApiCapabilities, Scope};
+use kube::{client::Client, core::GroupVersionKind, Api};
+use log::debug;
+use serde_json::{json, Value};
+
+use super::common::ScalingMachinery;
+
+/// Resolves the kind through discovery, it must be namespaced and expose the /scale subresource
+pub(crate) async fn discover_scalable(c: &Client, ar: &ApiResource) -> Result<ApiResource, Error> {
+    let gvk = GroupVersionKind::gvk(&ar.group, &ar.version, &ar.kind);
+    let (ar, caps) = pinned_kind(c, &gvk).await?;
+    validate_scalable(&ar, &caps)?;
+    Ok(ar)
+}
+
+fn validate_scalable(ar: &ApiResource, caps: &ApiCapabilities) -> Result<(), Error> {
+    if caps.scope != Scope::Namespaced {
+        return Err(Error::UserInputError(format!(
+            "{} {} is cluster scoped, only namespaced kinds can be scaled",
+            ar.api_version, ar.kind
+        )));
+    }
+    if !caps.subresources.iter().any(|(sr, _)| sr.plural == "scale") {
+        return Err(Error::UserInputError(format!(
+            "{} {} does not expose the /scale subresource",
+            ar.api_version, ar.kind
+        )));
+    }
+    Ok(())
+}
+
+/// Current replicas as reported by the /scale subresource
+async fn scale_replicas(api: &Api<DynamicObject>, name: &str) -> Result<i32, Error> {
+    let scale = api.get_scale(name).await?;
+    Ok(scale.spec.and_then(|s| s.replicas).unwrap_or(0))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Scalable<'a> {
+    pub(crate) resource: &'a ApiResource,
+    pub(crate) selection: &'a Selection,
+    pub(crate) replicas: Option<i32>,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Scalable<'a> {
+    pub fn new(
+        resource: &'a ApiResource,
+        selection: &'a Selection,
+        replicas: Option<i32>,
+        is_uptime: bool,
+    ) -> Self {
+        Scalable {
+            resource,
+            selection,
+            replicas,
+            is_uptime,
+        }
+    }
+}
+
+impl JMSExpression for DynamicObject {}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for Scalable<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let ar = discover_scalable(&c, self.resource).await?;
+        let api: Api<DynamicObject> = self.selection.api_with(c.clone(), &ar);
+        let list = api.list(&self.selection.list_params()).await?;
+        let mut list_scalable: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let name = item.metadata.name.unwrap();
+                let namespace = item.metadata.namespace.unwrap();
+                let ns_api: Api<DynamicObject> = Api::namespaced_with(c.clone(), &namespace, &ar);
+                let pat = ScalingMachinery {
+                    tobe_replicas: self.replicas,
+                    original_replicas: scale_replicas(&ns_api, &name).await?.to_string(),
+                    original_max_replicas: None,
+                    original_policy: None,
+                    name,
+                    namespace,
+                    annotations: item.metadata.annotations,
+                    resource_type: Resources::Scalable(ar.clone()),
+                    scale_state: Arc::clone(&scale_state),
+                };
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_scalable.push(scaled_res);
+                };
+            }
+        }
+        Ok(list_scalable)
+    }
+}
+
+/// Namespaced api of a kind scaled through its /scale subresource
+pub(crate) struct ScaleApi {
+    api: Api<DynamicObject>,
+    resource: ApiResource,
+}
+
+impl ScaleApi {
+    pub(crate) fn namespaced(c: Client, ns: &str, resource: &ApiResource) -> Self {
+        ScaleApi {
+            api: Api::namespaced_with(c, ns, resource),
+            resource: resource.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for ScaleApi {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching {}: {}", self.resource.kind, name);
+        // annotations first, so that the original count is kept if scaling fails
+        if let Some(metadata) = patch_value.get("metadata") {
+            self.api
+                .patch(
+                    name,
+                    &PatchParams::default(),
+                    &Patch::Merge(json!({ "metadata": metadata })),
+                )
+                .await?;
+        }
+        if let Some(spec) = patch_value.get("spec") {
+            self.api
+                .patch_scale(
+                    name,
+                    &PatchParams::default(),
+                    &Patch::Merge(json!({ "spec": spec })),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = self.api.list(&ListParams::default()).await?;
+        let mut list_scalable: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            let name = item.metadata.name.unwrap();
+            let namespace = item.metadata.namespace.unwrap();
+            debug!(
+                "Parsing {} {} since its in namespace {:?}",
+                self.resource.kind, name, namespace
+            );
+            let pat = ScalingMachinery {
+                tobe_replicas: replicas,
+                original_replicas: scale_replicas(&self.api, &name).await?.to_string(),
+                original_max_replicas: None,
+                original_policy: None,
+                name,
+                namespace,
+                annotations: item.metadata.annotations,
+                resource_type: Resources::Scalable(self.resource.clone()),
+                scale_state: Arc::clone(&scale_state),
+            };
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_scalable.push(scaled_res);
+            };
+        }
+        Ok(list_scalable)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let list = self.api.list(&ListParams::default()).await?;
+        for item in &list.items {
+            debug!(
+                "parsing {} resource {:?}",
+                self.resource.kind, item.metadata.name
+            );
+            let u = UpscaleMachinery {
+                replicas,
+                name: item.metadata.name.as_ref().unwrap().to_string(),
+                namespace: item.metadata.namespace.as_ref().unwrap().to_string(),
+                annotations: item.metadata.annotations.to_owned(),
+                resource_type: Resources::Scalable(self.resource.clone()),
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_scalable_requires_scale_subresource() {
+    let ar = ApiResource::from_gvk(&GroupVersionKind::gvk(
+        "kafka.strimzi.io",
+        "v1beta2",
+        "KafkaConnect",
+    ));
+    let scale = ApiResource {
+        plural: "scale".to_owned(),
+        ..ar.clone()
+    };
+    let caps = |scope, subresources| ApiCapabilities {
+        scope,
+        subresources,
+        operations: vec![],
+    };
+    assert!(validate_scalable(
+        &ar,
+        &caps(
+            Scope::Namespaced,
+            vec![(scale.clone(), caps(Scope::Namespaced, vec![]))]
+        )
+    )
+    .is_ok());
+    assert_eq!(
+        validate_scalable(&ar, &caps(Scope::Namespaced, vec![]))
+            .unwrap_err()
+            .to_string(),
+        "Invalid User Input: kafka.strimzi.io/v1beta2 KafkaConnect does not expose the /scale subresource"
+    );
+    assert_eq!(
+        validate_scalable(&ar, &caps(Scope::Cluster, vec![]))
+            .unwrap_err()
+            .to_string(),
+        "Invalid User Input: kafka.strimzi.io/v1beta2 KafkaConnect is cluster scoped, only namespaced kinds can be scaled"
+    );
+}
@@ -8,26 +8,39 @@ use log::debug;
 use serde_json::Value;
 
 use crate::controller::common::UpscaleMachinery;
-use crate::downscaler::{JMSExpression, ResourceExtension, Resources};
+use crate::downscaler::{JMSExpression, ResourceExtension, Resources, Selection};
 use crate::downscaler::{Res, ScaledResources};
 use crate::error::Error;
 use crate::ScaleState;
 
+use super::argocd::ArgoApps;
 use super::common::ScalingMachinery;
+use super::flux::suspend_owner;
+use super::hpa::HpaTargets;
 
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct StateSet<'a> {
-    pub(crate) expression: &'a str,
+    pub(crate) selection: &'a Selection,
     pub(crate) replicas: Option<i32>,
     pub(crate) is_uptime: bool,
+    pub(crate) suspend_flux: bool,
+    pub(crate) pause_argocd: bool,
 }
 
 impl<'a> StateSet<'a> {
-    pub fn new(expression: &'a str, replicas: Option<i32>, is_uptime: bool) -> Self {
+    pub fn new(
+        selection: &'a Selection,
+        replicas: Option<i32>,
+        is_uptime: bool,
+        suspend_flux: Option<bool>,
+        pause_argocd: Option<bool>,
+    ) -> Self {
         StateSet {
-            expression,
+            selection,
             replicas,
             is_uptime,
+            suspend_flux: suspend_flux.unwrap_or_default(),
+            pause_argocd: pause_argocd.unwrap_or_default(),
         }
     }
 }
@@ -41,22 +54,57 @@ impl Res for StateSet<'_> {
         c: Client,
         scale_state: Arc<ScaleState>,
     ) -> Result<Vec<ScaledResources>, Error> {
-        let api: Api<StatefulSet> = Api::all(c.clone());
-        let ss = api.list(&Default::default()).await.unwrap();
+        let api: Api<StatefulSet> = self.selection.api(c.clone());
+        let ss = api.list(&self.selection.list_params()).await.unwrap();
+        let hpas = HpaTargets::list(&self.selection.api(c.clone())).await?;
+        // the applications are only paused when the rule opts in with pause_argocd
+        let mut argo = match self.pause_argocd {
+            true => ArgoApps::list(&Api::all(c.clone())).await?,
+            false => ArgoApps::default(),
+        };
         let mut list_ss: Vec<ScaledResources> = vec![];
         for item in ss.items {
-            let result = item.parse(self.expression).await?;
+            let result = self.selection.matches(&item).await?;
             let original_count = (item.spec.unwrap().replicas.unwrap()).to_string();
             if result {
                 let pat = ScalingMachinery {
                     tobe_replicas: self.replicas,
                     original_replicas: original_count,
+                    original_max_replicas: None,
+                    original_policy: None,
                     name: item.metadata.name.unwrap(),
                     namespace: item.metadata.namespace.unwrap(),
                     annotations: item.metadata.annotations,
                     resource_type: Resources::StatefulSet,
                     scale_state: Arc::clone(&scale_state),
                 };
+                if let Some(scaled_res) = argo
+                    .pause_owner(
+                        c.clone(),
+                        item.metadata.labels.as_ref(),
+                        &pat,
+                        self.is_uptime,
+                    )
+                    .await?
+                {
+                    list_ss.push(scaled_res);
+                }
+                if self.suspend_flux {
+                    if let Some(scaled_res) = suspend_owner(
+                        c.clone(),
+                        item.metadata.labels.as_ref(),
+                        &pat,
+                        self.is_uptime,
+                    )
+                    .await?
+                    {
+                        list_ss.push(scaled_res);
+                    }
+                }
+                if let Some(scaled_res) = hpas.scale_paired(c.clone(), &pat, self.is_uptime).await?
+                {
+                    list_ss.push(scaled_res);
+                }
                 if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
                     list_ss.push(scaled_res);
                 };
@@ -95,6 +143,8 @@ impl ResourceExtension for Api<StatefulSet> {
             let pat = ScalingMachinery {
                 tobe_replicas: replicas,
                 original_replicas: original_count,
+                original_max_replicas: None,
+                original_policy: None,
                 name,
                 namespace,
                 annotations: item.metadata.annotations,
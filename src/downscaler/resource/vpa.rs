@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::controller::common::UpscaleMachinery;
+use crate::downscaler::{
+    JMSExpression, Res, ResourceExtension, Resources, ScaledResources, Selection,
+};
+use crate::error::Error;
+use crate::ScaleState;
+use async_trait::async_trait;
+use kube::api::{ListParams, Patch, PatchParams};
+use kube::{client::Client, Api, CustomResource, ResourceExt};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::common::{list_or_empty, ScalingMachinery};
+
+/// updatePolicy.updateMode of the vpa before it was switched off
+pub const ORIGINAL_UPDATE_MODE_ANNOTATION: &str = "kubesaver.com/original_update_mode";
+
+// vpa evicts the pods when the update mode is not set
+const DEFAULT_UPDATE_MODE: &str = "Auto";
+const UPDATE_MODE_OFF: &str = "Off";
+
+/// VerticalPodAutoscaler `autoscaling.k8s.io/v1`, only the update policy is typed
+#[derive(CustomResource, Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[kube(
+    group = "autoscaling.k8s.io",
+    version = "v1",
+    kind = "VerticalPodAutoscaler",
+    plural = "verticalpodautoscalers",
+    namespaced,
+    schema = "disabled"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct VerticalPodAutoscalerSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_policy: Option<Value>,
+    #[serde(flatten)]
+    pub rest: BTreeMap<String, Value>,
+}
+
+impl VerticalPodAutoscaler {
+    fn update_mode(&self) -> &str {
+        self.spec
+            .update_policy
+            .as_ref()
+            .and_then(|p| p.get("updateMode"))
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_UPDATE_MODE)
+    }
+}
+
+impl JMSExpression for VerticalPodAutoscaler {}
+
+/// Original updatePolicy.updateMode saved in the annotations when the vpa was switched off
+pub(crate) fn original_update_mode(
+    annotations: Option<&BTreeMap<String, String>>,
+) -> Option<Value> {
+    annotations?
+        .get(ORIGINAL_UPDATE_MODE_ANNOTATION)
+        .map(|mode| json!(mode))
+}
+
+/// Spec patch of the vpa, the update mode is `Off` at downtime and the original at uptime
+pub(crate) fn update_mode_patch(update_mode: Option<Value>) -> Value {
+    json!({ "updatePolicy": { "updateMode": update_mode.unwrap_or(json!(UPDATE_MODE_OFF)) } })
+}
+
+fn vpa_machinery(vpa: &VerticalPodAutoscaler, scale_state: Arc<ScaleState>) -> ScalingMachinery {
+    ScalingMachinery {
+        tobe_replicas: None,                // doesn't apply to vpa
+        original_replicas: "0".to_string(), // doesn't apply to vpa
+        original_max_replicas: None,
+        original_policy: Some(json!(vpa.update_mode())),
+        name: vpa.name_any(),
+        namespace: vpa.namespace().unwrap_or_default(),
+        annotations: vpa.metadata.annotations.clone(),
+        resource_type: Resources::Vpa,
+        scale_state,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Vpa<'a> {
+    pub(crate) selection: &'a Selection,
+    pub(crate) is_uptime: bool,
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Vpa<'a> {
+    pub fn new(selection: &'a Selection, is_uptime: bool) -> Self {
+        Vpa {
+            selection,
+            is_uptime,
+        }
+    }
+}
+
+#[async_trait]
+#[allow(clippy::needless_lifetimes)]
+impl<'a> Res for Vpa<'a> {
+    async fn downscale(
+        &self,
+        c: Client,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let api: Api<VerticalPodAutoscaler> = self.selection.api(c.clone());
+        let list = list_or_empty(
+            &api,
+            &self.selection.list_params(),
+            "vertical pod autoscalers",
+        )
+        .await?;
+        let mut list_vpa: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            // nothing to switch off when the vpa doesn't evict
+            if !self.is_uptime && item.update_mode() == UPDATE_MODE_OFF {
+                continue;
+            }
+            let result = self.selection.matches(&item).await?;
+            if result {
+                let pat = vpa_machinery(&item, Arc::clone(&scale_state));
+                if let Some(scaled_res) = pat.scaling_machinery(c.clone(), self.is_uptime).await? {
+                    list_vpa.push(scaled_res);
+                }
+            }
+        }
+        Ok(list_vpa)
+    }
+}
+
+#[async_trait]
+impl ResourceExtension for Api<VerticalPodAutoscaler> {
+    async fn patch_resource(&self, name: &str, patch_value: &Value) -> Result<(), Error> {
+        debug!("patching vpa: {}", name);
+        self.patch(name, &PatchParams::default(), &Patch::Merge(patch_value))
+            .await?;
+        Ok(())
+    }
+
+    async fn processor_scale_ns_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        c: Client,
+        is_uptime: bool,
+        scale_state: Arc<ScaleState>,
+    ) -> Result<Vec<ScaledResources>, Error> {
+        let list = list_or_empty(self, &ListParams::default(), "vertical pod autoscalers").await?;
+        let mut list_vpa: Vec<ScaledResources> = vec![];
+        for item in list.items {
+            if !is_uptime && item.update_mode() == UPDATE_MODE_OFF {
+                continue;
+            }
+            let pat = vpa_machinery(&item, Arc::clone(&scale_state));
+            if let Some(scaled_res) = pat.scaling_machinery(c.clone(), is_uptime).await? {
+                list_vpa.push(scaled_res);
+            }
+        }
+        Ok(list_vpa)
+    }
+
+    async fn controller_upscale_resource_items(
+        &self,
+        _replicas: Option<i32>,
+        client: Client,
+    ) -> Result<(), Error> {
+        let list = list_or_empty(self, &ListParams::default(), "vertical pod autoscalers").await?;
+        for vpa in &list.items {
+            debug!("parsing vpa resource {:?}", vpa.metadata.name);
+            let u = UpscaleMachinery {
+                replicas: None,
+                name: vpa.name_any(),
+                namespace: vpa.namespace().unwrap_or_default(),
+                annotations: vpa.metadata.annotations.to_owned(),
+                resource_type: Resources::Vpa,
+            };
+            u.upscale_machinery(client.clone()).await?
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn validate_update_mode() {
+    let vpa = |update_policy: Value| -> VerticalPodAutoscaler {
+        serde_json::from_value(json!({
+            "apiVersion": "autoscaling.k8s.io/v1",
+            "kind": "VerticalPodAutoscaler",
+            "metadata": { "name": "web", "namespace": "kuber" },
+            "spec": {
+                "targetRef": { "apiVersion": "apps/v1", "kind": "Deployment", "name": "web" },
+                "updatePolicy": update_policy
+            }
+        }))
+        .unwrap()
+    };
+    assert_eq!(
+        vpa(json!({ "updateMode": "Recreate" })).update_mode(),
+        "Recreate"
+    );
+    assert_eq!(vpa(json!({})).update_mode(), "Auto");
+    // switched off at downtime, the annotated original is restored at uptime
+    assert_eq!(
+        update_mode_patch(None),
+        json!({ "updatePolicy": { "updateMode": "Off" } })
+    );
+    let annotations = BTreeMap::from([(
+        ORIGINAL_UPDATE_MODE_ANNOTATION.to_string(),
+        "Recreate".to_string(),
+    )]);
+    assert_eq!(
+        update_mode_patch(original_update_mode(Some(&annotations))),
+        json!({ "updatePolicy": { "updateMode": "Recreate" } })
+    );
+    assert_eq!(original_update_mode(None), None);
+}
@@ -1,10 +1,17 @@
 use async_trait::async_trait;
-use kube::Client;
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use jmespath::ast::Ast;
+use k8s_openapi::api::core::v1::Namespace;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use kube::api::ListParams;
+use kube::core::{ApiResource, DynamicObject, GroupVersionKind, NamespaceResourceScope, Selector};
+use kube::{Api, Client, Resource, ResourceExt};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
-use std::{str::FromStr, sync::Arc};
+use std::{collections::BTreeSet, str::FromStr, sync::Arc};
 
 use crate::error::Error;
+use crate::schedule::Schedule;
 
 #[derive(Clone)]
 pub struct ScaleState {
@@ -12,6 +19,8 @@ pub struct ScaleState {
     pub(crate) scaleup_succcess_counter: prometheus::IntCounter,
     pub(crate) scaleup_error_counter: prometheus::IntCounter,
     pub(crate) scaledown_error_counter: prometheus::IntCounter,
+    pub(crate) rules_reload_success_counter: prometheus::IntCounter,
+    pub(crate) rules_reload_error_counter: prometheus::IntCounter,
 }
 
 impl Default for ScaleState {
@@ -20,40 +29,367 @@ impl Default for ScaleState {
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Default, Clone)]
 pub(crate) struct Rule {
     pub(crate) id: String,
-    pub(crate) uptime: String,
+    pub(crate) uptime: Option<Uptime>,
+    // cron expressions alternative to uptime, evaluated in the timezone
+    pub(crate) upschedule: Option<String>,
+    pub(crate) downschedule: Option<String>,
+    pub(crate) timezone: Option<String>,
+    // duration to upscale before the uptime starts, for example 15m
+    pub(crate) prewarm: Option<String>,
+    // duration to delay the downscale after the uptime ends
+    pub(crate) grace: Option<String>,
+    // optional with a selector or namespaceSelector
+    #[serde(default)]
     pub(crate) jmespath: String,
+    // label selector sent with the list calls, matchLabels and matchExpressions
+    pub(crate) selector: Option<LabelSelector>,
+    // labels of the namespaces of the resources, or of the namespaces themselves
+    #[serde(rename = "namespaceSelector")]
+    pub(crate) namespace_selector: Option<LabelSelector>,
     pub(crate) resource: Vec<String>,
     pub(crate) replicas: Option<i32>,
     pub(crate) slack_channel: Option<String>,
+    // names of the holiday calendars in addition to the default calendars
+    pub(crate) holidays: Option<Vec<String>>,
+    // opt out of all the holiday calendars
+    pub(crate) ignore_holidays: Option<bool>,
+    // date ranges overriding uptime, holidays and schedules
+    pub(crate) exceptions: Option<Vec<Exception>>,
+    // suspend or delete the running jobs of the cronjobs at downtime
+    pub(crate) active_jobs: Option<ActiveJobs>,
+    // suspend the flux HelmRelease or Kustomization applying the deployments and statefulsets
+    pub(crate) suspend_flux: Option<bool>,
+    // pause the automated sync of the argocd Application managing the deployments and statefulsets,
+    // or those of the namespaces
+    pub(crate) pause_argocd: Option<bool>,
+    // how the TenantSchedules of a namespace apply to the resources of the rule in that namespace,
+    // TenantSchedules are ignored when not set
+    pub(crate) tenants: Option<TenantPolicy>,
+    // window bounding the uptime the TenantSchedules can add with relax or replace
+    pub(crate) tenant_max_uptime: Option<Uptime>,
+    // compiled from jmespath when the rules are loaded
+    #[serde(skip)]
+    pub(crate) expression: Option<JmesPath>,
+    // parsed from uptime or upschedule/downschedule when the rules are deserialized
+    #[serde(skip)]
+    pub(crate) schedule: Schedule,
+    // parsed from tenant_max_uptime when the rules are deserialized
+    #[serde(skip)]
+    pub(crate) tenant_bound: Option<Schedule>,
+}
+
+/// Absolute date range `YYYY-MM-DD HH:MM` in the timezone forcing uptime or downtime
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Clone)]
+pub(crate) struct Exception {
+    pub(crate) name: String,
+    pub(crate) start: String,
+    pub(crate) end: String,
+    pub(crate) timezone: String,
+    pub(crate) action: ExceptionAction,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExceptionAction {
+    #[default]
+    Up,
+    Down,
+}
+
+/// Action on the running jobs of the cronjobs at downtime, suspended jobs are resumed at uptime
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ActiveJobs {
+    Suspend,
+    Delete,
+}
+
+/// Precedence of the TenantSchedules of a namespace over the schedule of a rule
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TenantPolicy {
+    // up only when the rule and every tenant schedule are up
+    Tighten,
+    // up when the rule or any tenant schedule is up
+    Relax,
+    // up when any tenant schedule is up, the rule's schedule is ignored
+    Replace,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+/// Holiday calendar, every date in the calendar is a downtime for the rules referring it
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
+pub(crate) struct Calendar {
+    pub(crate) name: String,
+    // ICS or yaml list of dates, relative paths are resolved from the directory of rules.yaml
+    pub(crate) path: String,
+    // default calendars apply to every rule which doesn't opt out
+    #[serde(default)]
+    pub(crate) default: bool,
+    #[serde(skip)]
+    pub(crate) dates: BTreeSet<NaiveDate>,
+}
+
+/// Uptime of a rule, either a single `<DAY>-<DAY> HH:MM-HH:MM TZ` window or a list of
+/// `<DAY>-<DAY> HH:MM-HH:MM` windows sharing one timezone.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(untagged)]
+pub(crate) enum Uptime {
+    Window(String),
+    Windows {
+        timezone: String,
+        windows: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for Uptime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Uptime::Window(window) => write!(f, "{}", window),
+            Uptime::Windows { timezone, windows } => {
+                write!(f, "{} {}", windows.join(", "), timezone)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct Rules {
+    #[serde(default)]
+    pub(crate) calendars: Vec<Calendar>,
+    #[serde(deserialize_with = "deserialize_rules")]
     pub(crate) rules: Vec<Rule>,
 }
 
+/// Parses the schedule of every rule, so that an invalid rule is rejected when the rules are loaded
+fn deserialize_rules<'de, D>(deserializer: D) -> Result<Vec<Rule>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut rules = Vec::<Rule>::deserialize(deserializer)?;
+    for rule in &mut rules {
+        rule.schedule = rule.parse_schedule().map_err(serde::de::Error::custom)?;
+        rule.tenant_bound = rule
+            .parse_tenant_bound()
+            .map_err(serde::de::Error::custom)?;
+    }
+    Ok(rules)
+}
+
 #[derive(Debug, Clone)]
 pub struct ScaledResources {
     pub(crate) name: String,
     pub(crate) namespace: String,
     pub(crate) kind: Resources,
+    pub(crate) action: ScaleAction,
+    // workload scaled together with the resource, for example the deployment targeted by an hpa
+    pub(crate) paired_with: Option<String>,
+}
+
+/// Action taken on the scaled resource, reported in the csv
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ScaleAction {
+    Downscale,
+    Upscale,
+    Delete,
+}
+
+impl std::fmt::Display for ScaleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScaleAction::Downscale => write!(f, "downscale"),
+            ScaleAction::Upscale => write!(f, "upscale"),
+            ScaleAction::Delete => write!(f, "delete"),
+        }
+    }
 }
 
 #[async_trait]
 pub trait JMSExpression {
-    async fn parse(&self, expression: &str) -> Result<bool, Error>
+    async fn parse(&self, expression: &JmesPath) -> Result<bool, Error>
     where
         Self: Serialize,
     {
-        let expr = jmespath::compile(expression).unwrap();
-        let str = serde_json::to_string(&self).unwrap();
-        let data = jmespath::Variable::from_json(&str).unwrap();
-        let result = expr.search(data).unwrap();
-        Ok(result.as_boolean().unwrap())
+        expression.search(self)
+    }
+}
+
+/// JMESPath expression compiled once when the rule is loaded, the owner names the rule in the errors
+#[derive(Debug, Clone, PartialEq)]
+pub struct JmesPath {
+    owner: String,
+    expression: jmespath::Expression<'static>,
+}
+
+// jmespath expressions are compared by their original string
+impl Eq for JmesPath {}
+
+impl JmesPath {
+    /// Compiles the expression, expressions which never evaluate to a boolean are rejected
+    pub fn compile(owner: &str, expression: &str) -> Result<Self, Error> {
+        let compiled = jmespath::compile(expression)
+            .map_err(|e| Error::UserInputError(format!("Invalid jmespath for {}: {}", owner, e)))?;
+        if !may_be_boolean(compiled.as_ast()) {
+            return Err(Error::UserInputError(format!(
+                "Invalid jmespath for {}: {} doesn't evaluate to a boolean",
+                owner, expression
+            )));
+        }
+        Ok(JmesPath {
+            owner: owner.to_owned(),
+            expression: compiled,
+        })
+    }
+
+    /// Searches the object without serializing it to a json string, a missing field doesn't match
+    pub(crate) fn search<T: Serialize + ?Sized>(&self, item: &T) -> Result<bool, Error> {
+        let result = self.expression.search(item).map_err(|e| {
+            Error::UserInputError(format!("jmespath of {} failed: {}", self.owner, e))
+        })?;
+        match result.as_boolean() {
+            Some(matches) => Ok(matches),
+            None if result.is_null() => Ok(false),
+            None => Err(Error::UserInputError(format!(
+                "jmespath {} of {} returned {}, expected a boolean",
+                self.expression, self.owner, result
+            ))),
+        }
+    }
+}
+
+// only comparisons, negations, boolean literals and functions, and their conjunctions are accepted,
+// a field may hold anything
+fn may_be_boolean(ast: &Ast) -> bool {
+    match ast {
+        Ast::Comparison { .. } | Ast::Not { .. } => true,
+        Ast::And { lhs, rhs, .. } | Ast::Or { lhs, rhs, .. } => {
+            may_be_boolean(lhs) && may_be_boolean(rhs)
+        }
+        Ast::Literal { value, .. } => value.is_boolean(),
+        Ast::Function { name, .. } => {
+            matches!(name.as_str(), "contains" | "starts_with" | "ends_with")
+        }
+        _ => false,
+    }
+}
+
+/// Resources selected by a rule or an Upscaler, the label selector is sent to the API server with the
+/// list calls and the jmespath is evaluated on the listed objects
+#[derive(Debug, PartialEq, Eq, Default, Clone)]
+pub struct Selection {
+    pub(crate) expression: Option<JmesPath>,
+    pub(crate) labels: Option<Selector>,
+    // namespaces matched by the namespace selector
+    pub(crate) namespaces: Option<BTreeSet<String>>,
+    // namespaces with tenant schedules, scaled separately
+    pub(crate) excluded_namespaces: BTreeSet<String>,
+}
+
+impl Selection {
+    /// Selection of a jmespath only
+    pub fn jmespath(owner: &str, jmespath: &str) -> Result<Self, Error> {
+        Ok(Selection {
+            expression: Some(JmesPath::compile(owner, jmespath)?),
+            ..Default::default()
+        })
+    }
+
+    /// Lists the namespaces matching the namespace selector, the selectors are validated when the
+    /// rules are loaded
+    pub async fn new(
+        client: Client,
+        expression: Option<&JmesPath>,
+        selector: Option<&LabelSelector>,
+        namespace_selector: Option<&LabelSelector>,
+    ) -> Result<Self, Error> {
+        let namespaces = match namespace_selector {
+            Some(namespace_selector) => {
+                let api: Api<Namespace> = Api::all(client);
+                let lp = ListParams::default().labels_from(&label_selector(namespace_selector)?);
+                Some(
+                    api.list(&lp)
+                        .await?
+                        .items
+                        .iter()
+                        .map(|ns| ns.name_any())
+                        .collect(),
+                )
+            }
+            None => None,
+        };
+        Ok(Selection {
+            expression: expression.cloned(),
+            labels: selector.map(label_selector).transpose()?,
+            namespaces,
+            excluded_namespaces: BTreeSet::new(),
+        })
+    }
+
+    /// List params of the resources with the label selector
+    pub(crate) fn list_params(&self) -> ListParams {
+        match &self.labels {
+            Some(labels) => ListParams::default().labels_from(labels),
+            None => ListParams::default(),
+        }
+    }
+
+    /// Api listing the resources, namespaced when a single namespace is selected so that the
+    /// namespaces of the tenants are listed separately instead of the whole cluster
+    pub(crate) fn api<K>(&self, client: Client) -> Api<K>
+    where
+        K: Resource<Scope = NamespaceResourceScope>,
+        K::DynamicType: Default,
+    {
+        match self.single_namespace() {
+            Some(namespace) => Api::namespaced(client, namespace),
+            None => Api::all(client),
+        }
     }
+
+    /// Same as `api` for the kinds discovered at runtime, which are namespaced
+    pub(crate) fn api_with(&self, client: Client, ar: &ApiResource) -> Api<DynamicObject> {
+        match self.single_namespace() {
+            Some(namespace) => Api::namespaced_with(client, namespace, ar),
+            None => Api::all_with(client, ar),
+        }
+    }
+
+    pub(crate) fn single_namespace(&self) -> Option<&str> {
+        match &self.namespaces {
+            Some(namespaces) if namespaces.len() == 1 => namespaces.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the listed resource is in a selected namespace and matches the jmespath
+    pub(crate) async fn matches<K>(&self, item: &K) -> Result<bool, Error>
+    where
+        K: JMSExpression + ResourceExt + Serialize + Sync,
+    {
+        // namespaces are cluster scoped and selected by their own name
+        let namespace = item.namespace().unwrap_or_else(|| item.name_any());
+        if self.excluded_namespaces.contains(&namespace)
+            || self
+                .namespaces
+                .as_ref()
+                .is_some_and(|namespaces| !namespaces.contains(&namespace))
+        {
+            return Ok(false);
+        }
+        // a rule with only selectors matches every listed resource
+        match &self.expression {
+            Some(expression) => item.parse(expression).await,
+            None => Ok(true),
+        }
+    }
+}
+
+/// Converts the matchLabels and matchExpressions to a label selector of the API
+pub(crate) fn label_selector(selector: &LabelSelector) -> Result<Selector, Error> {
+    Selector::try_from(selector.clone())
+        .map_err(|e| Error::UserInputError(format!("Invalid label selector: {}", e)))
 }
 
 #[async_trait]
@@ -62,13 +398,23 @@ pub trait Res {
         -> Result<Vec<ScaledResources>, Error>;
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Resources {
     Deployment,
     StatefulSet,
     Namespace,
     CronJob,
+    Job,
     Hpa,
+    Rollout,
+    ScaledObject,
+    DaemonSet,
+    HelmRelease,
+    Kustomization,
+    Application,
+    Vpa,
+    // any `group/version/Kind` scaled through its /scale subresource
+    Scalable(ApiResource),
 }
 
 #[async_trait]
@@ -93,28 +439,68 @@ pub trait ResourceExtension: Send + Sync {
 impl FromStr for Resources {
     type Err = Error;
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.contains('/') {
+            return parse_scalable(input);
+        }
         match input.to_lowercase().as_str() {
             "deployments" | "deployment" => Ok(Resources::Deployment),
             "statefulset"| "statefulsets" => Ok(Resources::StatefulSet),
             "namespace" | "namespaces" => Ok(Resources::Namespace),
             "cronjob" | "cronjobs" => Ok(Resources::CronJob),
+            "job" | "jobs" => Ok(Resources::Job),
             "hpa" | "horizontalpodautoscaler" | "horizontalpodautoscalers" => Ok(Resources::Hpa),
+            "rollout" | "rollouts" => Ok(Resources::Rollout),
+            "scaledobject" | "scaledobjects" => Ok(Resources::ScaledObject),
+            "daemonset" | "daemonsets" => Ok(Resources::DaemonSet),
+            "helmrelease" | "helmreleases" => Ok(Resources::HelmRelease),
+            "kustomization" | "kustomizations" => Ok(Resources::Kustomization),
+            "application" | "applications" => Ok(Resources::Application),
+            "vpa" | "verticalpodautoscaler" | "verticalpodautoscalers" => Ok(Resources::Vpa),
             e => Err(Error::UserInputError(format!(
-                "Unsupported resource type {}, Currently supports only Deployment, StatefulSet, Namespace, Hpa, CronJob",
+                "Unsupported resource type {}, Currently supports only Deployment, StatefulSet, Namespace, Hpa, Vpa, CronJob, Job, Rollout, ScaledObject, DaemonSet, HelmRelease, Kustomization, Application or group/version/Kind",
                 e
             ))),
         }
     }
 }
 
+/// Parses `group/version/Kind`, or `version/Kind` for the core group, the plural is resolved by discovery
+fn parse_scalable(input: &str) -> Result<Resources, Error> {
+    let gvk = match input.split('/').collect::<Vec<_>>()[..] {
+        [group, version, kind] if !group.is_empty() => {
+            Some(GroupVersionKind::gvk(group, version, kind))
+        }
+        [version, kind] => Some(GroupVersionKind::gvk("", version, kind)),
+        _ => None,
+    };
+    match gvk {
+        Some(gvk) if !gvk.version.is_empty() && !gvk.kind.is_empty() => {
+            Ok(Resources::Scalable(ApiResource::from_gvk(&gvk)))
+        }
+        _ => Err(Error::UserInputError(format!(
+            "Invalid resource type {}, expected group/version/Kind for example kafka.strimzi.io/v1beta2/KafkaConnect",
+            input
+        ))),
+    }
+}
+
 impl std::fmt::Display for Resources {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
+        match self {
             Resources::Deployment => write!(f, "Deployment"),
             Resources::StatefulSet => write!(f, "StatefulSet"),
             Resources::Namespace => write!(f, "Namespace"),
             Resources::CronJob => write!(f, "CronJob"),
+            Resources::Job => write!(f, "Job"),
             Resources::Hpa => write!(f, "Hpa"),
+            Resources::Rollout => write!(f, "Rollout"),
+            Resources::ScaledObject => write!(f, "ScaledObject"),
+            Resources::DaemonSet => write!(f, "DaemonSet"),
+            Resources::HelmRelease => write!(f, "HelmRelease"),
+            Resources::Kustomization => write!(f, "Kustomization"),
+            Resources::Application => write!(f, "Application"),
+            Resources::Vpa => write!(f, "Vpa"),
+            Resources::Scalable(ar) => write!(f, "{}", ar.kind),
         }
     }
 }
@@ -181,6 +567,19 @@ fn test_valid_input_resource_hpa() {
     );
 }
 
+#[test]
+fn test_valid_input_resource_vpa() {
+    assert_eq!(Resources::from_str("Vpa").unwrap(), Resources::Vpa);
+    assert_eq!(
+        Resources::from_str("VerticalPodAutoscaler").unwrap(),
+        Resources::Vpa
+    );
+    assert_eq!(
+        Resources::from_str("verticalpodautoscalers").unwrap(),
+        Resources::Vpa
+    );
+}
+
 #[test]
 fn test_valid_input_resource_statefulset() {
     assert_eq!(
@@ -209,11 +608,197 @@ fn test_valid_input_resource_statefulset() {
     );
 }
 
+#[test]
+fn test_valid_input_resource_rollout() {
+    assert_eq!(Resources::from_str("Rollout").unwrap(), Resources::Rollout);
+    assert_eq!(Resources::from_str("rollouts").unwrap(), Resources::Rollout);
+}
+
+#[test]
+fn test_valid_input_resource_scaledobject() {
+    assert_eq!(
+        Resources::from_str("ScaledObject").unwrap(),
+        Resources::ScaledObject
+    );
+    assert_eq!(
+        Resources::from_str("scaledobjects").unwrap(),
+        Resources::ScaledObject
+    );
+}
+
+#[test]
+fn test_valid_input_resource_daemonset() {
+    assert_eq!(
+        Resources::from_str("DaemonSet").unwrap(),
+        Resources::DaemonSet
+    );
+    assert_eq!(
+        Resources::from_str("daemonsets").unwrap(),
+        Resources::DaemonSet
+    );
+}
+
+#[test]
+fn test_valid_input_resource_flux() {
+    assert_eq!(
+        Resources::from_str("HelmRelease").unwrap(),
+        Resources::HelmRelease
+    );
+    assert_eq!(
+        Resources::from_str("helmreleases").unwrap(),
+        Resources::HelmRelease
+    );
+    assert_eq!(
+        Resources::from_str("Kustomization").unwrap(),
+        Resources::Kustomization
+    );
+    assert_eq!(
+        Resources::from_str("kustomizations").unwrap(),
+        Resources::Kustomization
+    );
+}
+
+#[test]
+fn test_valid_input_resource_application() {
+    assert_eq!(
+        Resources::from_str("Application").unwrap(),
+        Resources::Application
+    );
+    assert_eq!(
+        Resources::from_str("applications").unwrap(),
+        Resources::Application
+    );
+}
+
+#[test]
+fn test_valid_input_resource_job() {
+    assert_eq!(Resources::from_str("Job").unwrap(), Resources::Job);
+    assert_eq!(Resources::from_str("jobs").unwrap(), Resources::Job);
+}
+
 #[test]
 fn test_invalid() {
     let res = Resources::from_str("StatefulSet1");
     assert_eq!(
         res.unwrap_err().to_string(),
-        "Invalid User Input: Unsupported resource type statefulset1, Currently supports only Deployment, StatefulSet, Namespace, Hpa, CronJob".to_string()
+        "Invalid User Input: Unsupported resource type statefulset1, Currently supports only Deployment, StatefulSet, Namespace, Hpa, Vpa, CronJob, Job, Rollout, ScaledObject, DaemonSet, HelmRelease, Kustomization, Application or group/version/Kind".to_string()
+    )
+}
+
+#[test]
+fn test_valid_input_resource_scalable() {
+    match Resources::from_str("kafka.strimzi.io/v1beta2/KafkaConnect").unwrap() {
+        Resources::Scalable(ar) => {
+            assert_eq!(ar.group, "kafka.strimzi.io");
+            assert_eq!(ar.api_version, "kafka.strimzi.io/v1beta2");
+            assert_eq!(ar.kind, "KafkaConnect");
+        }
+        r => panic!("expected a scalable resource, got {}", r),
+    }
+    match Resources::from_str("v1/ReplicationController").unwrap() {
+        Resources::Scalable(ar) => {
+            assert_eq!(ar.group, "");
+            assert_eq!(ar.api_version, "v1");
+            assert_eq!(ar.kind, "ReplicationController");
+        }
+        r => panic!("expected a scalable resource, got {}", r),
+    }
+}
+
+#[test]
+fn test_invalid_input_resource_scalable() {
+    for r in ["apps/v1/", "/v1/Deployment", "a/b/c/d", "v1/"] {
+        assert_eq!(
+            Resources::from_str(r).unwrap_err().to_string(),
+            format!("Invalid User Input: Invalid resource type {}, expected group/version/Kind for example kafka.strimzi.io/v1beta2/KafkaConnect", r)
+        );
+    }
+}
+
+#[tokio::test]
+async fn validate_selection() {
+    use k8s_openapi::api::apps::v1::Deployment;
+    let rule: Rule = serde_yaml::from_str(
+        r#"
+id: selectors
+uptime: Mon-Fri 07:00-19:00 Australia/Sydney
+selector:
+  matchLabels:
+    app: web
+  matchExpressions:
+    - key: tier
+      operator: In
+      values: [backend, frontend]
+namespaceSelector:
+  matchLabels:
+    team: a
+resource:
+  - Deployment
+"#,
     )
+    .unwrap();
+    assert_eq!(rule.jmespath, "");
+    let selection = Selection {
+        labels: rule
+            .selector
+            .as_ref()
+            .map(label_selector)
+            .transpose()
+            .unwrap(),
+        namespaces: Some(BTreeSet::from(["team-a".to_owned()])),
+        ..Default::default()
+    };
+    assert_eq!(
+        selection.list_params().label_selector.as_deref(),
+        Some("app=web,tier in (backend,frontend)")
+    );
+    let deployment = |namespace: &str| -> Deployment {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "web", "namespace": namespace, "labels": { "app": "web" } }
+        }))
+        .unwrap()
+    };
+    // the label selector is applied by the API server, the namespaces and the jmespath on the list
+    assert!(selection.matches(&deployment("team-a")).await.unwrap());
+    assert!(!selection.matches(&deployment("team-b")).await.unwrap());
+    let selection = Selection {
+        expression: Some(
+            JmesPath::compile("rule id selectors", "metadata.labels.app == 'api'").unwrap(),
+        ),
+        ..selection
+    };
+    assert!(!selection.matches(&deployment("team-a")).await.unwrap());
+}
+
+#[test]
+fn validate_jmespath() {
+    use k8s_openapi::api::apps::v1::Deployment;
+    let deployment: Deployment = serde_json::from_value(serde_json::json!({
+        "metadata": { "name": "web", "namespace": "kuber", "labels": { "app": "web" } },
+        "spec": { "paused": true, "selector": {}, "template": {} }
+    }))
+    .unwrap();
+    let search = |expression: &str| {
+        JmesPath::compile("rule id web", expression).and_then(|e| e.search(&deployment))
+    };
+    assert!(search("metadata.labels.app == 'web' && contains(metadata.name, 'we')").unwrap());
+    assert!(search("spec.paused == `true`").unwrap());
+    // a missing field doesn't match
+    assert!(!search("spec.template.spec.hostNetwork == `true`").unwrap());
+    assert_eq!(
+        search("metadata.labels.app ==").unwrap_err().to_string().lines().next(),
+        Some("Invalid User Input: Invalid jmespath for rule id web: Parse error: Unexpected nud token -- found Eof (line 0, column 22)")
+    );
+    assert_eq!(
+        search("metadata.labels").unwrap_err().to_string(),
+        "Invalid User Input: Invalid jmespath for rule id web: metadata.labels doesn't evaluate to a boolean"
+    );
+    assert!(search("spec.paused").is_err());
+    assert!(search("metadata.labels.app || `true`").is_err());
+    // an ordering comparison of strings is null in jmespath
+    assert!(!search("metadata.name > 'a'").unwrap());
+    assert_eq!(
+        search("metadata.labels.*").unwrap_err().to_string(),
+        "Invalid User Input: Invalid jmespath for rule id web: metadata.labels.* doesn't evaluate to a boolean"
+    );
 }
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+
+use crate::error::Error;
+
+/// Source of the current time against which the rules are evaluated
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Wall clock of the system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock fixed at an instant, to evaluate the rules at an arbitrary time
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Parses the RFC 3339 timestamp, for example `2024-09-06T18:59:00+10:00`
+pub fn parse_rfc3339(now: &str) -> Result<DateTime<Utc>, Error> {
+    DateTime::parse_from_rfc3339(now)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| {
+            Error::UserInputError(format!(
+                "Invalid timestamp {}, expected RFC 3339 for example 2024-09-06T18:59:00+10:00: {}",
+                now, e
+            ))
+        })
+}
+
+#[test]
+fn validate_fixed_clock() {
+    let now = parse_rfc3339("2024-09-06T18:59:00+10:00").unwrap();
+    assert_eq!(
+        FixedClock(now).now().to_rfc3339(),
+        "2024-09-06T08:59:00+00:00"
+    );
+}
+
+#[test]
+fn validate_invalid_rfc3339() {
+    assert!(parse_rfc3339("2024-09-06 18:59")
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid User Input: Invalid timestamp 2024-09-06 18:59"));
+}
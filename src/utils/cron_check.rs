@@ -0,0 +1,208 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use croner::Cron;
+use log::debug;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::time_check::Margins;
+
+/// Parses the standard 5 field cron expression
+fn parse_cron(expression: &str) -> Result<Cron, Error> {
+    Cron::from_str(expression).map_err(|e| {
+        Error::UserInputError(format!("Invalid cron expression {}: {}", expression, e))
+    })
+}
+
+/// Maximum number of occurrences of an expression considered while searching for the next transitions
+const MAX_EDGES: usize = 1000;
+
+/// Returns the most recent occurrence of the cron expression at or before `dt`
+fn last_transition(cron: &Cron, dt: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    // an expression which never occured before dt is not an error, it's just not a transition yet
+    cron.find_previous_occurrence(dt, true).ok()
+}
+
+/// Cron expressions `upschedule` and `downschedule` evaluated in the timezone
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    upschedule: Cron,
+    downschedule: Cron,
+    tz: Tz,
+}
+
+impl CronSchedule {
+    pub fn new(upschedule: &str, downschedule: &str, timezone: &str) -> Result<Self, Error> {
+        Ok(CronSchedule {
+            upschedule: parse_cron(upschedule)?,
+            downschedule: parse_cron(downschedule)?,
+            tz: timezone.parse().map_err(|_| {
+                Error::UserInputError(format!(
+                    "Invalid timezone {}, expected an Olson timezone for example Australia/Sydney",
+                    timezone
+                ))
+            })?,
+        })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// Returns the upschedules moved earlier by prewarm and downschedules moved later by grace,
+    /// which fall after from and at or before to
+    pub(crate) fn edges(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        margins: Margins,
+    ) -> Vec<DateTime<Utc>> {
+        let occurrences = |cron: &Cron, shift: TimeDelta| -> Vec<DateTime<Utc>> {
+            cron.iter_after((from + shift).with_timezone(&self.tz))
+                .map(|dt| dt.with_timezone(&Utc) - shift)
+                .take_while(|dt| *dt <= to)
+                .take(MAX_EDGES)
+                .collect()
+        };
+        let mut edges = occurrences(&self.upschedule, margins.prewarm);
+        edges.extend(occurrences(&self.downschedule, -margins.grace));
+        edges
+    }
+
+    /// Returns true if the most recent transition is an upscale,
+    /// upscales are moved earlier by prewarm and downscales later by grace
+    pub(crate) fn is_uptime_at(&self, now: DateTime<Utc>, margins: Margins) -> Result<bool, Error> {
+        let dt = now.with_timezone(&self.tz);
+        let last_up = last_transition(&self.upschedule, &(dt + margins.prewarm))
+            .map(|up| up - margins.prewarm);
+        let last_down = last_transition(&self.downschedule, &(dt - margins.grace))
+            .map(|down| down + margins.grace);
+        debug!(
+            "last upschedule {:?} and last downschedule {:?} for current local time {}",
+            last_up, last_down, dt
+        );
+        match (last_up, last_down) {
+            (Some(up), Some(down)) => Ok(up > down),
+            (Some(_), None) => Ok(true),
+            (None, Some(_)) => Ok(false),
+            (None, None) => Err(Error::UserInputError(format!(
+                "Neither upschedule {} nor downschedule {} has ever occured",
+                self.upschedule, self.downschedule
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod croncheck_unit_test {
+    use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+    use chrono_tz::Australia::Sydney;
+
+    use crate::cron_check::CronSchedule;
+    use crate::error::Error;
+    use crate::time_check::Margins;
+
+    struct CronCheck {
+        schedule: CronSchedule,
+        margins: Margins,
+        now: DateTime<Utc>,
+    }
+
+    impl CronCheck {
+        fn is_uptime(&self) -> Result<bool, Error> {
+            self.schedule.is_uptime_at(self.now, self.margins)
+        }
+    }
+
+    fn cron_check(
+        upschedule: &str,
+        downschedule: &str,
+        (year, month, day, hour, min): (i32, u32, u32, u32, u32),
+    ) -> CronCheck {
+        let nd = NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap();
+        CronCheck {
+            schedule: CronSchedule::new(upschedule, downschedule, "Australia/Sydney").unwrap(),
+            margins: Margins::default(),
+            now: Sydney.from_local_datetime(&nd).unwrap().with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    // Upschedule  : 07:00 first Monday of the month
+    // Downschedule: 19:00 first Monday of the month
+    fn test_first_monday_of_month() {
+        let (up, down) = ("0 7 * * MON#1", "0 19 * * MON#1");
+        // Datetime: 02-Sep-2024 Day: first Monday Time: 08:00 AM
+        // Expected : Resources should be UP
+        assert!(cron_check(up, down, (2024, 9, 2, 8, 0))
+            .is_uptime()
+            .unwrap());
+        // Datetime: 02-Sep-2024 Day: first Monday Time: 07:00 PM
+        // Expected : Resources should be DOWN
+        assert!(!cron_check(up, down, (2024, 9, 2, 19, 0))
+            .is_uptime()
+            .unwrap());
+        // Datetime: 09-Sep-2024 Day: second Monday Time: 08:00 AM
+        // Expected : Resources should be DOWN
+        assert!(!cron_check(up, down, (2024, 9, 9, 8, 0))
+            .is_uptime()
+            .unwrap());
+    }
+
+    #[test]
+    // Upschedule  : every 2 hours on odd days
+    // Downschedule: an hour after every upschedule
+    fn test_every_two_hours_on_odd_days() {
+        let (up, down) = ("0 */2 1-31/2 * *", "0 1-23/2 1-31/2 * *");
+        // Datetime: 03-Sep-2024 Time: 10:30 AM
+        // Expected : Resources should be UP
+        assert!(cron_check(up, down, (2024, 9, 3, 10, 30))
+            .is_uptime()
+            .unwrap());
+        // Datetime: 03-Sep-2024 Time: 11:30 AM
+        // Expected : Resources should be DOWN
+        assert!(!cron_check(up, down, (2024, 9, 3, 11, 30))
+            .is_uptime()
+            .unwrap());
+        // Datetime: 04-Sep-2024 Time: 10:30 AM
+        // Expected : Resources should be DOWN, last transition was 03-Sep 23:00
+        assert!(!cron_check(up, down, (2024, 9, 4, 10, 30))
+            .is_uptime()
+            .unwrap());
+    }
+
+    #[test]
+    fn test_invalid_cron_expression() {
+        let err = CronSchedule::new("blah", "0 19 * * *", "Australia/Sydney").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid User Input: Invalid cron expression blah"));
+    }
+
+    #[test]
+    // Upschedule  : 07:00 on weekdays with prewarm 15m
+    // Downschedule: 19:00 on weekdays with grace 30m
+    fn test_prewarm_and_grace() {
+        let (up, down) = ("0 7 * * MON-FRI", "0 19 * * MON-FRI");
+        let margins = Margins::parse(Some("15m"), Some("30m")).unwrap();
+        let check = |dt| CronCheck {
+            margins,
+            ..cron_check(up, down, dt)
+        };
+        // Datetime: 02-Sep-2024 Day: Monday Time: 06:50 AM
+        // Expected : Resources should be UP
+        assert!(check((2024, 9, 2, 6, 50)).is_uptime().unwrap());
+        // Datetime: 02-Sep-2024 Day: Monday Time: 06:40 AM
+        // Expected : Resources should be DOWN
+        assert!(!check((2024, 9, 2, 6, 40)).is_uptime().unwrap());
+        // Datetime: 02-Sep-2024 Day: Monday Time: 07:20 PM
+        // Expected : Resources should be UP
+        assert!(check((2024, 9, 2, 19, 20)).is_uptime().unwrap());
+        // Datetime: 02-Sep-2024 Day: Monday Time: 07:40 PM
+        // Expected : Resources should be DOWN
+        assert!(!check((2024, 9, 2, 19, 40)).is_uptime().unwrap());
+    }
+}
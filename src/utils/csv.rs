@@ -9,6 +9,8 @@ pub fn generate_csv(resources: &[ScaledResources], file_name: &str) -> Result<()
             r.kind.to_string(),
             r.namespace.to_string(),
             r.name.to_string(),
+            r.action.to_string(),
+            r.paired_with.clone().unwrap_or_default(),
         ])?;
     }
     wtr.flush()?;
@@ -26,9 +28,11 @@ fn validate_csv_generation() {
         name: "test-kuber2-deploy1".to_string(),
         namespace: "kuber1".to_string(),
         kind: crate::Resources::Deployment,
+        action: crate::ScaleAction::Downscale,
+        paired_with: None,
     };
     let c = generate_csv(&[s], "file");
     assert_eq!(c.unwrap(), ());
-    assert_eq!(std::path::Path::new("/tmp/file.csv").exists(), true);
-    std::fs::remove_file(format!("/tmp/file.csv")).unwrap()
+    assert!(std::path::Path::new("/tmp/file.csv").exists());
+    std::fs::remove_file("/tmp/file.csv").unwrap()
 }
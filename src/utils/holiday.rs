@@ -0,0 +1,209 @@
+use chrono::{Datelike, Days, NaiveDate};
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::error::Error;
+
+fn parse_date(date: &str) -> Result<NaiveDate, Error> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date.get(..8).unwrap_or(date), "%Y%m%d"))
+        .map_err(|e| Error::UserInputError(format!("Invalid holiday date {}: {}", date, e)))
+}
+
+// yearly events without COUNT or UNTIL are expanded until the end of this year
+const RRULE_LAST_YEAR: i32 = 2100;
+
+/// Yearly recurrence of an event on the date of its DTSTART
+#[derive(Debug, PartialEq, Eq)]
+struct Recurrence {
+    interval: u32,
+    count: Option<u32>,
+    until: Option<NaiveDate>,
+}
+
+/// Parses the RRULE of an event, only `FREQ=YEARLY` on the month and day of DTSTART is supported
+fn parse_rrule(rrule: &str, start: NaiveDate) -> Result<Recurrence, Error> {
+    let unsupported = || {
+        Error::UserInputError(format!(
+            "Unsupported RRULE {}, only FREQ=YEARLY on the date of DTSTART is supported",
+            rrule
+        ))
+    };
+    let mut recurrence = Recurrence {
+        interval: 1,
+        count: None,
+        until: None,
+    };
+    let mut yearly = false;
+    for part in rrule.split(';') {
+        let (key, value) = part.split_once('=').ok_or_else(unsupported)?;
+        match key {
+            "FREQ" => yearly = value == "YEARLY",
+            "INTERVAL" => recurrence.interval = value.parse().map_err(|_| unsupported())?,
+            "COUNT" => recurrence.count = Some(value.parse().map_err(|_| unsupported())?),
+            "UNTIL" => recurrence.until = Some(parse_date(value)?),
+            // public holiday feeds repeat the date of DTSTART
+            "BYMONTH" if value.parse() == Ok(start.month()) => {}
+            "BYMONTHDAY" if value.parse() == Ok(start.day()) => {}
+            "WKST" => {}
+            _ => return Err(unsupported()),
+        }
+    }
+    if !yearly || recurrence.interval == 0 {
+        return Err(unsupported());
+    }
+    Ok(recurrence)
+}
+
+/// Returns the start dates of the occurrences, invalid dates such as Feb 29 of a non leap year are skipped
+fn occurrences(start: NaiveDate, recurrence: &Recurrence) -> Vec<NaiveDate> {
+    let last = recurrence
+        .until
+        .unwrap_or(NaiveDate::from_ymd_opt(RRULE_LAST_YEAR, 12, 31).unwrap());
+    let mut dates = vec![];
+    let mut year = start.year();
+    while year <= last.year()
+        && recurrence
+            .count
+            .is_none_or(|count| dates.len() < count as usize)
+    {
+        match start.with_year(year) {
+            Some(date) if date > last => break,
+            Some(date) => dates.push(date),
+            None => {}
+        }
+        year += recurrence.interval as i32;
+    }
+    dates
+}
+
+/// Parses the all-day events of an ICS calendar, multi-day events are expanded until DTEND(exclusive)
+/// and yearly recurring events are expanded with their RRULE
+pub fn parse_ics(content: &str) -> Result<BTreeSet<NaiveDate>, Error> {
+    // unfold the lines continued with a leading whitespace as per RFC 5545
+    let unfolded = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut dates = BTreeSet::new();
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+    let mut rrule: Option<String> = None;
+    for line in unfolded.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // strip parameters, for example DTSTART;VALUE=DATE
+        let key = key.split(';').next().unwrap_or_default();
+        match (key, value.trim()) {
+            ("BEGIN", "VEVENT") => {
+                start = None;
+                end = None;
+                rrule = None;
+            }
+            ("DTSTART", value) => start = Some(parse_date(value)?),
+            ("DTEND", value) => end = Some(parse_date(value)?),
+            ("RRULE", value) => rrule = Some(value.to_owned()),
+            ("END", "VEVENT") => {
+                let Some(start) = start else {
+                    return Err(Error::UserInputError(
+                        "Holiday calendar event without DTSTART".to_string(),
+                    ));
+                };
+                let days = end.map_or(1, |end| (end - start).num_days().max(1)) as u64;
+                let starts = match &rrule {
+                    Some(rrule) => occurrences(start, &parse_rrule(rrule, start)?),
+                    None => vec![start],
+                };
+                for start in starts {
+                    for day in 0..days {
+                        dates.insert(start + Days::new(day));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(dates)
+}
+
+/// Parses a yaml list of dates in format YYYY-MM-DD
+pub fn parse_yaml(content: &str) -> Result<BTreeSet<NaiveDate>, Error> {
+    let dates: Vec<String> = serde_yaml::from_str(content)
+        .map_err(|e| Error::UserInputError(format!("Invalid holiday calendar: {}", e)))?;
+    dates.iter().map(|d| parse_date(d)).collect()
+}
+
+/// Reads the holiday calendar, files with extension .ics are parsed as ICS calendar, otherwise yaml
+pub fn read_calendar(path: &Path) -> Result<BTreeSet<NaiveDate>, Error> {
+    let content = fs::read_to_string(path)?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("ics") => parse_ics(&content),
+        _ => parse_yaml(&content),
+    }
+    .map_err(|e| match e {
+        Error::UserInputError(e) => {
+            Error::UserInputError(format!("{} in calendar {}", e, path.display()))
+        }
+        e => e,
+    })
+}
+
+#[test]
+fn validate_parse_ics_calendar() {
+    let ics = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20241225\r\nDTEND;VALUE=DATE:20241227\r\nSUMMARY:Christmas\r\n  and Boxing Day\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20250101\r\nSUMMARY:New Year\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+    let dates = parse_ics(ics).unwrap();
+    assert_eq!(
+        dates.into_iter().collect::<Vec<_>>(),
+        vec![
+            NaiveDate::from_ymd_opt(2024, 12, 25).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 12, 26).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+        ]
+    );
+}
+
+#[test]
+fn validate_parse_ics_yearly_rrule() {
+    let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20241225\r\nDTEND;VALUE=DATE:20241227\r\nRRULE:FREQ=YEARLY;BYMONTH=12;BYMONTHDAY=25\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20240229\r\nRRULE:FREQ=YEARLY;COUNT=2\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+    let dates = parse_ics(ics).unwrap();
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    assert!(dates.contains(&date(2024, 12, 26)));
+    assert!(dates.contains(&date(2030, 12, 25)));
+    assert!(dates.contains(&date(2100, 12, 26)));
+    assert!(!dates.contains(&date(2101, 12, 25)));
+    // Feb 29 is skipped in the non leap years
+    assert!(dates.contains(&date(2028, 2, 29)));
+    assert!(!dates.contains(&date(2032, 2, 29)));
+    assert_eq!(
+        occurrences(
+            date(2024, 1, 26),
+            &parse_rrule("FREQ=YEARLY;INTERVAL=2;UNTIL=20290101", date(2024, 1, 26)).unwrap()
+        ),
+        vec![date(2024, 1, 26), date(2026, 1, 26), date(2028, 1, 26)]
+    );
+    // holidays on a week day of a month are not supported
+    assert_eq!(
+        parse_rrule("FREQ=YEARLY;BYMONTH=6;BYDAY=2MO", date(2025, 6, 9))
+            .unwrap_err()
+            .to_string(),
+        "Invalid User Input: Unsupported RRULE FREQ=YEARLY;BYMONTH=6;BYDAY=2MO, only FREQ=YEARLY on the date of DTSTART is supported"
+    );
+    assert!(parse_rrule("FREQ=MONTHLY", date(2025, 6, 9)).is_err());
+}
+
+#[test]
+fn validate_parse_yaml_calendar() {
+    let dates = parse_yaml("- 2024-12-25\n- '2025-01-01'\n").unwrap();
+    assert!(dates.contains(&NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()));
+    assert!(dates.contains(&NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()));
+    assert_eq!(dates.len(), 2);
+}
+
+#[test]
+fn validate_parse_yaml_calendar_invalid_date() {
+    let dates = parse_yaml("- 2024-13-25\n");
+    assert!(dates
+        .unwrap_err()
+        .to_string()
+        .starts_with("Invalid User Input: Invalid holiday date 2024-13-25"));
+}
@@ -1,5 +1,9 @@
+pub mod clock;
+pub mod cron_check;
 pub mod csv;
 pub mod error;
+pub mod holiday;
 pub mod parser;
+pub mod schedule;
 pub mod slack;
 pub mod time_check;
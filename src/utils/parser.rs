@@ -1,13 +1,21 @@
+use chrono::{DateTime, Utc};
 use clap::Parser;
 use clap::{error::ErrorKind, CommandFactory};
 use k8s_openapi::api::{
-    apps::v1::Deployment, apps::v1::StatefulSet, autoscaling::v1::HorizontalPodAutoscaler,
-    batch::v1::CronJob,
+    apps::v1::DaemonSet, apps::v1::Deployment, apps::v1::StatefulSet,
+    autoscaling::v2::HorizontalPodAutoscaler, batch::v1::CronJob, batch::v1::Job,
 };
 use kube::{Api, Client};
 use log::{error, info};
 use std::{env, fs, path::Path, str::FromStr};
 
+use crate::clock::parse_rfc3339;
+use crate::downscaler::resource::argocd::Application;
+use crate::downscaler::resource::flux::{HelmRelease, Kustomization};
+use crate::downscaler::resource::keda::ScaledObject;
+use crate::downscaler::resource::rollout::Rollout;
+use crate::downscaler::resource::scale::ScaleApi;
+use crate::downscaler::resource::vpa::VerticalPodAutoscaler;
 use crate::error::Error;
 use crate::{ResourceExtension, Resources};
 
@@ -31,6 +39,12 @@ pub struct Args {
     /// supply --comm_details=<slack_org_group>, this arg is mandatory if --comm_type=slack is set
     #[clap(long, value_parser)]
     pub comm_details: Option<String>,
+    /// supply --now=<RFC3339> together with --dry-run to evaluate the rules at the time instead of the current time
+    #[clap(long, value_parser = parse_rfc3339, requires = "dry_run")]
+    pub now: Option<DateTime<Utc>>,
+    /// supply --dry-run to only log whether the rules would scale up or down
+    #[clap(long)]
+    pub dry_run: bool,
 }
 impl Args {
     pub fn new() -> Self {
@@ -63,6 +77,8 @@ impl Args {
             debug: cli.debug,
             comm_type: comm.0,
             comm_details: comm.1,
+            now: cli.now,
+            dry_run: cli.dry_run,
         }
     }
 }
@@ -164,13 +180,22 @@ impl ContextData {
 pub fn dynamic_resource_type(
     c: Client,
     ns: &str,
-    resource_type: Resources,
+    resource_type: &Resources,
 ) -> Option<Box<dyn ResourceExtension + Send + Sync>> {
     match resource_type {
         Resources::Deployment => Some(Box::new(Api::<Deployment>::namespaced(c, ns))),
         Resources::StatefulSet => Some(Box::new(Api::<StatefulSet>::namespaced(c, ns))),
         Resources::CronJob => Some(Box::new(Api::<CronJob>::namespaced(c, ns))),
+        Resources::Job => Some(Box::new(Api::<Job>::namespaced(c, ns))),
         Resources::Hpa => Some(Box::new(Api::<HorizontalPodAutoscaler>::namespaced(c, ns))),
+        Resources::Rollout => Some(Box::new(Api::<Rollout>::namespaced(c, ns))),
+        Resources::DaemonSet => Some(Box::new(Api::<DaemonSet>::namespaced(c, ns))),
+        Resources::ScaledObject => Some(Box::new(Api::<ScaledObject>::namespaced(c, ns))),
+        Resources::HelmRelease => Some(Box::new(Api::<HelmRelease>::namespaced(c, ns))),
+        Resources::Kustomization => Some(Box::new(Api::<Kustomization>::namespaced(c, ns))),
+        Resources::Application => Some(Box::new(Api::<Application>::namespaced(c, ns))),
+        Resources::Vpa => Some(Box::new(Api::<VerticalPodAutoscaler>::namespaced(c, ns))),
+        Resources::Scalable(ar) => Some(Box::new(ScaleApi::namespaced(c, ns, ar))),
         Resources::Namespace => None, //nothing to do
     }
 }
@@ -208,8 +233,24 @@ fn test_input_resource_namespace() {
     assert_eq!(f, Some(Resources::Namespace));
 }
 
+#[test]
+fn test_input_resource_rollout() {
+    let f = check_input_resource("rollout");
+    assert_eq!(f, Some(Resources::Rollout));
+}
+
 #[test]
 fn test_input_resource_unsupported() {
     let f = check_input_resource("pod");
     assert_eq!(f, None);
 }
+
+#[test]
+fn test_now_requires_dry_run() {
+    let args = Args::try_parse_from(["kube-saver", "--now=2026-01-01T03:00:00Z"]);
+    assert_eq!(args.unwrap_err().kind(), ErrorKind::MissingRequiredArgument);
+    let args =
+        Args::try_parse_from(["kube-saver", "--now=2026-01-01T03:00:00Z", "--dry-run"]).unwrap();
+    assert!(args.dry_run);
+    assert_eq!(args.now, parse_rfc3339("2026-01-01T03:00:00Z").ok());
+}
@@ -0,0 +1,282 @@
+use chrono::{DateTime, Days, TimeDelta, Utc};
+use chrono_tz::Tz;
+
+use crate::cron_check::CronSchedule;
+use crate::error::Error;
+use crate::time_check::{Margins, UpTimeWindow};
+
+/// Transitions are searched within these many days from now
+pub const TRANSITION_HORIZON_DAYS: u64 = 62;
+
+/// Next instants at which the resources are upscaled and downscaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transitions {
+    pub upscale: Option<DateTime<Utc>>,
+    pub downscale: Option<DateTime<Utc>>,
+}
+
+impl Transitions {
+    /// Returns the first candidates after now right after which the uptime changes
+    pub fn search<F>(
+        now: DateTime<Utc>,
+        mut candidates: Vec<DateTime<Utc>>,
+        is_uptime: F,
+    ) -> Result<Self, Error>
+    where
+        F: Fn(DateTime<Utc>) -> Result<bool, Error>,
+    {
+        candidates.sort();
+        candidates.dedup();
+        let mut transitions = Transitions::default();
+        let mut current = is_uptime(now)?;
+        for candidate in candidates.into_iter().filter(|c| *c > now) {
+            // windows are up after the start, so evaluate right after the candidate
+            let next = is_uptime(candidate + TimeDelta::seconds(1))?;
+            if next == current {
+                continue;
+            }
+            match next {
+                true => transitions.upscale = transitions.upscale.or(Some(candidate)),
+                false => transitions.downscale = transitions.downscale.or(Some(candidate)),
+            }
+            current = next;
+            if transitions.upscale.is_some() && transitions.downscale.is_some() {
+                break;
+            }
+        }
+        Ok(transitions)
+    }
+
+    /// Returns the next downscale if the resources are up, otherwise the next upscale in the timezone
+    pub fn describe_next(&self, is_uptime: bool, tz: Tz) -> String {
+        let (event, next) = match is_uptime {
+            true => ("downscale", self.downscale),
+            false => ("upscale", self.upscale),
+        };
+        match next {
+            Some(next) => format!(
+                "next {} at {}",
+                event,
+                next.with_timezone(&tz).format("%a %Y-%m-%d %H:%M %Z")
+            ),
+            None => format!("no {} within {} days", event, TRANSITION_HORIZON_DAYS),
+        }
+    }
+}
+
+/// Parsed and validated schedule deciding when the resources are up
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schedule {
+    trigger: Trigger,
+    margins: Margins,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Trigger {
+    // resources are up if any of the window matches
+    Windows(Vec<UpTimeWindow>),
+    Cron(Box<CronSchedule>),
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Trigger::Windows(vec![])
+    }
+}
+
+impl Schedule {
+    /// Parses the uptime windows `<DAY>-<DAY> HH:MM-HH:MM TZ`
+    pub fn windows(windows: &[String], margins: Margins) -> Result<Self, Error> {
+        if windows.is_empty() {
+            return Err(Error::UserInputError(
+                "uptime windows cannot be empty".to_owned(),
+            ));
+        }
+        Ok(Schedule {
+            trigger: Trigger::Windows(
+                windows
+                    .iter()
+                    .map(|w| w.parse())
+                    .collect::<Result<_, _>>()?,
+            ),
+            margins,
+        })
+    }
+
+    /// Parses the cron expressions `upschedule` and `downschedule` in the timezone
+    pub fn cron(
+        upschedule: &str,
+        downschedule: &str,
+        timezone: &str,
+        margins: Margins,
+    ) -> Result<Self, Error> {
+        Ok(Schedule {
+            trigger: Trigger::Cron(Box::new(CronSchedule::new(
+                upschedule,
+                downschedule,
+                timezone,
+            )?)),
+            margins,
+        })
+    }
+
+    /// Returns the timezone in which the schedule is evaluated
+    pub fn timezone(&self) -> Option<Tz> {
+        match &self.trigger {
+            Trigger::Windows(windows) => windows.first().map(|w| w.timezone()),
+            Trigger::Cron(cron) => Some(cron.timezone()),
+        }
+    }
+
+    /// Returns the instants after from and at or before to, at which the uptime may change
+    pub fn edges(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        match &self.trigger {
+            Trigger::Windows(windows) => windows
+                .iter()
+                .flat_map(|w| w.edges(from, to, self.margins))
+                .collect(),
+            Trigger::Cron(cron) => cron.edges(from, to, self.margins),
+        }
+    }
+
+    /// Returns the next upscale and downscale of the schedule after now
+    pub fn next_transitions(&self, now: DateTime<Utc>) -> Result<Transitions, Error> {
+        let edges = self.edges(now, now + Days::new(TRANSITION_HORIZON_DAYS));
+        Transitions::search(now, edges, |dt| self.is_uptime(dt))
+    }
+
+    /// Returns true if its a uptime at the instant
+    pub fn is_uptime(&self, now: DateTime<Utc>) -> Result<bool, Error> {
+        match &self.trigger {
+            Trigger::Windows(windows) => {
+                Ok(windows.iter().any(|w| w.is_uptime_at(now, self.margins)))
+            }
+            Trigger::Cron(cron) => cron.is_uptime_at(now, self.margins),
+        }
+    }
+}
+
+#[test]
+fn validate_schedule_windows() {
+    let s = Schedule::windows(
+        &[
+            String::from("Mon-Sun 23:58-23:59 Australia/Sydney"),
+            String::from("Mon-Sun 00:00-23:59 Australia/Sydney"),
+        ],
+        Margins::default(),
+    )
+    .unwrap();
+    assert_eq!(s.timezone(), Some(chrono_tz::Australia::Sydney));
+    assert!(s.is_uptime(Utc::now()).unwrap());
+}
+
+#[test]
+fn validate_schedule_empty_windows() {
+    assert_eq!(
+        Schedule::windows(&[], Margins::default())
+            .unwrap_err()
+            .to_string(),
+        "Invalid User Input: uptime windows cannot be empty"
+    );
+}
+
+#[test]
+fn validate_next_transitions_windows() {
+    use crate::clock::parse_rfc3339;
+    let s = Schedule::windows(
+        &[
+            String::from("Mon-Fri 07:00-19:00 Australia/Sydney"),
+            String::from("Sat-Sat 09:00-13:00 Australia/Sydney"),
+        ],
+        Margins::parse(Some("15m"), None).unwrap(),
+    )
+    .unwrap();
+    // Friday 18:59 in Sydney
+    let t = s
+        .next_transitions(parse_rfc3339("2024-09-06T18:59:00+10:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-06T19:00:00+10:00").unwrap())
+    );
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-07T08:45:00+10:00").unwrap())
+    );
+    // Saturday 14:00 in Sydney, next upscale is Monday 06:45
+    let t = s
+        .next_transitions(parse_rfc3339("2024-09-07T14:00:00+10:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-09T06:45:00+10:00").unwrap())
+    );
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-09T19:00:00+10:00").unwrap())
+    );
+}
+
+#[test]
+fn validate_next_transitions_overlapping_windows() {
+    use crate::clock::parse_rfc3339;
+    // windows overlapping each other are a single uptime
+    let s = Schedule::windows(
+        &[
+            String::from("Mon-Mon 07:00-12:00 UTC"),
+            String::from("Mon-Mon 11:00-19:00 UTC"),
+        ],
+        Margins::default(),
+    )
+    .unwrap();
+    let t = s
+        .next_transitions(parse_rfc3339("2024-09-09T08:00:00Z").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-09-09T19:00:00Z").unwrap())
+    );
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-09-16T07:00:00Z").unwrap())
+    );
+}
+
+#[test]
+fn validate_next_transitions_cron() {
+    use crate::clock::parse_rfc3339;
+    let s = Schedule::cron(
+        "0 7 * * MON#1",
+        "0 19 * * MON#1",
+        "Australia/Sydney",
+        Margins::default(),
+    )
+    .unwrap();
+    // Monday 02-Sep-2024 20:00 in Sydney, next first Monday is 07-Oct-2024 after DST starts
+    let t = s
+        .next_transitions(parse_rfc3339("2024-09-02T20:00:00+10:00").unwrap())
+        .unwrap();
+    assert_eq!(
+        t.upscale,
+        Some(parse_rfc3339("2024-10-07T07:00:00+11:00").unwrap())
+    );
+    assert_eq!(
+        t.downscale,
+        Some(parse_rfc3339("2024-10-07T19:00:00+11:00").unwrap())
+    );
+}
+
+#[test]
+fn validate_describe_next_transition() {
+    use crate::clock::parse_rfc3339;
+    let t = Transitions {
+        upscale: Some(parse_rfc3339("2024-09-09T06:45:00+10:00").unwrap()),
+        downscale: None,
+    };
+    let tz = chrono_tz::Australia::Sydney;
+    assert_eq!(
+        t.describe_next(false, tz),
+        "next upscale at Mon 2024-09-09 06:45 AEST"
+    );
+    assert_eq!(t.describe_next(true, tz), "no downscale within 62 days");
+}
@@ -100,13 +100,7 @@ impl<'a> Slack<'a> {
             Err(Error::ReqwestError { source: e })
         } else {
             let r = response?.json::<SlackResponse>().await?;
-            if r.error.is_none() {
-                info!(
-                    "Sent alert message to slack channel {}, org {}",
-                    self.channel, self.slack_org
-                );
-            } else {
-                let slack_response_error_code = r.error.unwrap();
+            if let Some(slack_response_error_code) = r.error {
                 error!(
                     "Something went wrong while sending slack notification to channel: {}, org: {}, Error: {} \n visit {} for more details on error",
                     self.channel, self.slack_org,slack_response_error_code, SLACK_UPLOAD_API_ERROR
@@ -115,6 +109,11 @@ impl<'a> Slack<'a> {
                     "Error Code {}",
                     slack_response_error_code
                 )));
+            } else {
+                info!(
+                    "Sent alert message to slack channel {}, org {}",
+                    self.channel, self.slack_org
+                );
             }
             Ok(())
         }
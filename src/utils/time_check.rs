@@ -1,217 +1,292 @@
-use chrono::{DateTime, Datelike, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, Days, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeDelta,
+    TimeZone, Utc,
+};
 use chrono_tz::Tz;
-use log::{debug, info};
-use regex::Captures;
-use std::{process::exit, str::FromStr};
-use tracing::error;
+use log::debug;
+use regex::Regex;
+use std::str::FromStr;
 
 use crate::error::Error;
 
-pub fn current_day(day: &str) -> u32 {
+/// `<DAY>[-<DAY>][,<DAY>[-<DAY>]...] HH:MM-HH:MM TZ`
+pub const UPTIME_REGEX: &str = r"^(?P<days>[a-zA-Z]{3}(?:-[a-zA-Z]{3})?(?:,[a-zA-Z]{3}(?:-[a-zA-Z]{3})?)*) (\d\d):(\d\d)-(\d\d):(\d\d) (?P<tz>[a-zA-Z/_]+)$";
+
+pub fn current_day(day: &str) -> Result<u32, Error> {
     match day {
-        "Mon" => 0,
-        "Tue" => 1,
-        "Wed" => 2,
-        "Thu" => 3,
-        "Fri" => 4,
-        "Sat" => 5,
-        "Sun" => 6,
-        _ => exit(1),
+        "Mon" => Ok(0),
+        "Tue" => Ok(1),
+        "Wed" => Ok(2),
+        "Thu" => Ok(3),
+        "Fri" => Ok(4),
+        "Sat" => Ok(5),
+        "Sun" => Ok(6),
+        _ => Err(Error::UserInputError(format!(
+            "Invalid day {}, expected one of Mon, Tue, Wed, Thu, Fri, Sat, Sun",
+            day
+        ))),
     }
 }
 
-struct Timer {
-    dt: DateTime<Tz>,
-    config_date_low_hour: NaiveDateTime,
-    config_date_high_hour: NaiveDateTime,
-}
-impl Timer {
-    pub fn cmp_time(&self) -> bool {
-        if self.dt.naive_local() > self.config_date_low_hour
-            && self.dt.naive_local() <= self.config_date_high_hour
-        {
-            // the uptime is between the range
-            // start upscaling
-            debug!(
-                "current {} is range  {} &  {}",
-                self.dt, self.config_date_low_hour, self.config_date_high_hour
-            );
-            true
-        } else {
-            // the downtime is between the range
-            // start downscaling
-            debug!(
-                "current {} is not in range  {} &  {}",
-                self.dt, self.config_date_low_hour, self.config_date_high_hour
-            );
-            debug!("Current rules states, its a downtime for configured resources");
-            false
+/// Resolves the local datetime in the timezone into an instant.
+/// A local time skipped by a DST gap is interpreted with the offset before the gap (as in RFC 5545),
+/// for example 02:30 on the day Sydney moves from 02:00 to 03:00 is 03:30 AEDT.
+/// A local time repeated by a DST overlap is the earliest of the two instants.
+pub fn resolve_local(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) => dt,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => {
+            // DST transitions are months apart, a day before the gap is always on the offset before the gap
+            let before = resolve_local(tz, local - Days::new(1));
+            let offset = before.offset().fix();
+            tz.from_utc_datetime(&(local - offset))
         }
     }
 }
 
-struct UpTimeCheck {
-    week_start: u32,
-    week_end: u32,
-    low_hour: u32,
-    low_min: u32,
-    high_hour: u32,
-    high_min: u32,
-    dt: DateTime<Tz>,
+/// Lead time before an uptime starts and delay after it ends
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Margins {
+    // resources are upscaled this long before the uptime starts
+    pub prewarm: TimeDelta,
+    // resources are downscaled this long after the uptime ends
+    pub grace: TimeDelta,
+}
+
+impl Margins {
+    /// Parses the durations, for example `15m` or `1h30m`
+    pub fn parse(prewarm: Option<&str>, grace: Option<&str>) -> Result<Margins, Error> {
+        Ok(Margins {
+            prewarm: prewarm.map(parse_duration).transpose()?.unwrap_or_default(),
+            grace: grace.map(parse_duration).transpose()?.unwrap_or_default(),
+        })
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<TimeDelta, Error> {
+    humantime::parse_duration(duration)
+        .ok()
+        .and_then(|d| TimeDelta::from_std(d).ok())
+        .ok_or_else(|| {
+            Error::UserInputError(format!(
+                "Invalid duration {}, expected for example 15m or 1h30m",
+                duration
+            ))
+        })
 }
 
-impl UpTimeCheck {
-    fn is_uptime(&self) -> Result<bool, Error> {
-        // this is hack to check if end time > 12:00 AM and complx hack for downscaling.
-        // For example start time Mon 7AM  - 2 AM
-        let complex_high_time = if let LocalResult::None =
-            Utc.with_ymd_and_hms(2020, 1, 1, self.high_hour, self.high_min, 0)
-        {
-            error!("Invalid time {}:{}", self.high_hour, self.high_min);
-            return Err(Error::UserInputError("Invalid time".to_string()));
+/// Uptime window opening on the configured week days at start and closing at end,
+/// windows whose end is before the start extend past midnight into the next day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpTimeWindow {
+    days: Vec<u32>,
+    start: NaiveTime,
+    end: NaiveTime,
+    tz: Tz,
+}
+
+impl UpTimeWindow {
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// Returns the instants at which the window opening on the date starts and ends
+    fn window_on(&self, date: NaiveDate) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+        if !self.days.contains(&date.weekday().num_days_from_monday()) {
+            return None;
+        }
+        let end_date = if self.end < self.start {
+            date.checked_add_days(Days::new(1))?
         } else {
-            Utc.with_ymd_and_hms(2020, 1, 1, self.high_hour, self.high_min, 0)
-                .unwrap()
+            date
         };
+        Some((
+            resolve_local(&self.tz, date.and_time(self.start)),
+            resolve_local(&self.tz, end_date.and_time(self.end)),
+        ))
+    }
 
-        let complex_low_time = if let LocalResult::None =
-            Utc.with_ymd_and_hms(2020, 1, 1, self.low_hour, self.low_min, 0)
-        {
-            error!("Invalid time {}:{}", self.low_hour, self.low_min);
-            return Err(Error::UserInputError("Invalid time".to_string()));
-        } else {
-            Utc.with_ymd_and_hms(2020, 1, 1, self.low_hour, self.low_min, 0)
-                .unwrap()
+    /// Returns the starts moved earlier by prewarm and the ends moved later by grace of the windows,
+    /// which fall after from and at or before to
+    pub(crate) fn edges(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        margins: Margins,
+    ) -> Vec<DateTime<Utc>> {
+        if self.start == self.end {
+            return vec![];
+        }
+        let past = Days::new(margins.grace.num_days() as u64 + 1);
+        let future = Days::new(margins.prewarm.num_days() as u64 + 1);
+        let (Some(first), Some(last)) = (
+            from.with_timezone(&self.tz)
+                .date_naive()
+                .checked_sub_days(past),
+            to.with_timezone(&self.tz)
+                .date_naive()
+                .checked_add_days(future),
+        ) else {
+            return vec![];
         };
-        // current datetime which is updated as per conditions
-        let mut config_date_low_hour = self.get_hms(self.low_hour, self.low_min, self.dt.day())?;
-        let mut config_date_high_hour =
-            self.get_hms(self.high_hour, self.high_min, self.dt.day())?;
-
-        // check if the current day is configured in the input week range
-        if self.dt.weekday().num_days_from_monday() >= self.week_start
-            && self.dt.weekday().num_days_from_monday() <= self.week_end
-            && complex_high_time > complex_low_time
-        {
-            info!(
-                "config_date_low_hour: {} config_date_high_hour: {} and current local time {} ",
-                config_date_low_hour,
-                config_date_high_hour,
-                self.dt.naive_local()
-            );
-
-            let t = Timer {
-                dt: self.dt,
-                config_date_low_hour,
-                config_date_high_hour,
-            };
-            Ok(t.cmp_time())
-        } else if complex_high_time < complex_low_time {
-            info!("current rule is for rules whose end time is extending midnight");
-            // check if current day has passed the end day of rule
-            // for example RULE = Mon-Fri 7AM - 01AM, and its sat 01:10 AM
-            if self.dt.weekday().num_days_from_monday() == self.week_end + 1 {
-                config_date_low_hour =
-                    self.get_hms(self.low_hour, self.low_min, self.dt.day() - 1)?;
-                let t = Timer {
-                    dt: self.dt,
-                    config_date_low_hour,
-                    config_date_high_hour,
-                };
-                return Ok(t.cmp_time());
-            }
-            // for example RULE = Mon-Fri 7 - 02 AM, and its Mon 1 AM
-            else if self.dt.weekday().num_days_from_monday() == self.week_start
-                && self.dt.hour() < complex_high_time.hour()
-            {
-                // downscaling
-                return Ok(false);
-            } else if self.dt.weekday().num_days_from_monday() >= self.week_start
-                && self.dt.weekday().num_days_from_monday() <= self.week_end
-            {
-                // if current time has crossed 12 AM but less than or equals to high hour
-                if self.dt.hour() <= complex_high_time.hour() {
-                    //below condition is needed if minutes are involved, for example scale down is 2:30 AM
-                    config_date_low_hour =
-                        self.get_hms(self.low_hour, self.low_min, self.dt.day() - 1)?;
-                    let t = Timer {
-                        dt: self.dt,
-                        config_date_low_hour,
-                        config_date_high_hour,
-                    };
-                    return Ok(t.cmp_time());
-                }
-                // if current time is less 12 AM but greater than low hour
-                else if self.dt.hour() >= complex_low_time.hour() {
-                    // below is needed if there are minutes involved, for example start time is 7:30 AM
-                    config_date_high_hour =
-                        self.get_hms(self.high_hour, self.high_min, self.dt.day() + 1)?;
-                    let t = Timer {
-                        dt: self.dt,
-                        config_date_low_hour,
-                        config_date_high_hour,
-                    };
-                    return Ok(t.cmp_time());
-                }
-            }
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter_map(|date| self.window_on(date))
+            .flat_map(|(start, end)| {
+                [
+                    (start - margins.prewarm).with_timezone(&Utc),
+                    (end + margins.grace).with_timezone(&Utc),
+                ]
+            })
+            .filter(|edge| from < *edge && *edge <= to)
+            .collect()
+    }
 
-            Ok(false)
-        } else {
-            // current day is not configured in the uptime
-            debug!("current day is not configured in the uptime,hence downscaling");
-            Ok(false)
+    /// Returns true if the instant is after the start and before or at the end of any window,
+    /// with the start moved earlier by prewarm and the end moved later by grace
+    pub(crate) fn is_uptime_at(&self, now: DateTime<Utc>, margins: Margins) -> bool {
+        if self.start == self.end {
+            debug!("uptime window start and end are same, hence downscaling");
+            return false;
         }
+        let today = now.with_timezone(&self.tz).date_naive();
+        // windows opened yesterday extend past midnight, windows opening in the future are prewarmed and
+        // windows which already ended are in grace
+        let past = Days::new(margins.grace.num_days() as u64 + 1);
+        let future = Days::new(margins.prewarm.num_days() as u64 + 1);
+        let (Some(first), Some(last)) =
+            (today.checked_sub_days(past), today.checked_add_days(future))
+        else {
+            return false;
+        };
+        first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter_map(|date| self.window_on(date))
+            .any(|(start, end)| {
+                let (start, end) = (start - margins.prewarm, end + margins.grace);
+                let is_up = start < now && now <= end;
+                debug!(
+                    "current {} is {}in range {} & {}",
+                    now.with_timezone(&self.tz),
+                    if is_up { "" } else { "not " },
+                    start,
+                    end
+                );
+                is_up
+            })
     }
+}
 
-    fn get_hms(&self, hr: u32, min: u32, day: u32) -> Result<NaiveDateTime, Error> {
-        if let Some(ddd) = NaiveDate::from_ymd_opt(self.dt.year(), self.dt.month(), day) {
-            if let Some(hms) = ddd.and_hms_milli_opt(hr, min, 0, 0) {
-                return Ok(hms);
-            } else {
-                error!("invalid  time{}:{}", hr, min);
+/// Returns the week days configured as comma separated days or ranges, for example `Mon,Wed-Fri`.
+/// Ranges wrap around the week, `Fri-Mon` is Fri,Sat,Sun,Mon
+pub fn week_days(days: &str) -> Result<Vec<u32>, Error> {
+    let mut week_days = vec![];
+    for d in days.split(',') {
+        let (start, end) = match d.split_once('-') {
+            Some((start, end)) => (current_day(start)?, current_day(end)?),
+            None => (current_day(d)?, current_day(d)?),
+        };
+        let mut day = start;
+        loop {
+            if !week_days.contains(&day) {
+                week_days.push(day);
             }
-        } else {
-            error!(
-                "invalid date {}/{}/{}",
-                self.dt.year(),
-                self.dt.month(),
-                day
-            )
+            if day == end {
+                break;
+            }
+            day = (day + 1) % 7;
         }
-        Err(Error::UserInputError("Invalid datetime".to_string()))
     }
+    Ok(week_days)
+}
+
+fn hour_min(hour: &str, min: &str) -> Result<NaiveTime, Error> {
+    NaiveTime::from_hms_opt(hour.parse()?, min.parse()?, 0).ok_or_else(|| {
+        Error::UserInputError(format!(
+            "Invalid time {}:{}, expected 00:00 to 23:59",
+            hour, min
+        ))
+    })
+}
+
+impl FromStr for UpTimeWindow {
+    type Err = Error;
+    /// Parses the window `<DAY>-<DAY> HH:MM-HH:MM TZ`
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        let re = Regex::new(UPTIME_REGEX).map_err(|e| Error::UserInputError(e.to_string()))?;
+        let m = re.captures(window).ok_or_else(|| Error::UserInputError(String::from("Input datetime format didn't match <DAY>-<DAY> <START_TIME_HR>:<START_TIME_MIN>-<END_TIME_HR>:<END_TIME_MIN> <TIMEZONE>, Refer sample example in README.md")))?;
+        let in_window = |e: Error| match e {
+            Error::UserInputError(e) => Error::UserInputError(format!("uptime {}: {}", window, e)),
+            e => e,
+        };
+        Ok(UpTimeWindow {
+            days: week_days(&m["days"]).map_err(in_window)?,
+            start: hour_min(&m[2], &m[3]).map_err(in_window)?,
+            end: hour_min(&m[4], &m[5]).map_err(in_window)?,
+            tz: m["tz"].parse().map_err(|_| {
+                Error::UserInputError(format!(
+                    "uptime {}: Invalid timezone {}, expected an Olson timezone for example Australia/Sydney",
+                    window, &m["tz"]
+                ))
+            })?,
+        })
+    }
+}
+
+/// Parses the local datetime `YYYY-MM-DD HH:MM` in the timezone
+pub fn parse_local_datetime(datetime: &str, tz: &Tz) -> Result<DateTime<Tz>, Error> {
+    let naive = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").map_err(|e| {
+        Error::UserInputError(format!(
+            "Input datetime {} didn't match YYYY-MM-DD HH:MM: {}",
+            datetime, e
+        ))
+    })?;
+    Ok(resolve_local(tz, naive))
 }
 
-pub fn is_uptime(m: Captures) -> Result<bool, Error> {
-    let week_start = current_day(&m[1]);
-    let week_end = current_day(&m[2]);
-    let low_hour: u32 = FromStr::from_str(&m[3])?;
-    let low_min: u32 = FromStr::from_str(&m[4])?;
-    let high_hour: u32 = FromStr::from_str(&m[5])?;
-    let high_min: u32 = FromStr::from_str(&m[6])?;
-    let config_tz: &str = &m["tz"];
-    let tz: Tz = config_tz.parse()?;
-    // get the current datetime based on the timezone
-    let dt: DateTime<Tz> = Utc::now().with_timezone(&tz);
-
-    let upt_chk = UpTimeCheck {
-        week_start,
-        week_end,
-        low_hour,
-        low_min,
-        high_hour,
-        high_min,
-        dt,
-    };
-    upt_chk.is_uptime()
+/// Returns true if now is between start(inclusive) and end(exclusive) `YYYY-MM-DD HH:MM` in the timezone
+pub fn is_in_range(
+    start: &str,
+    end: &str,
+    timezone: &str,
+    now: DateTime<Utc>,
+) -> Result<bool, Error> {
+    let tz: Tz = timezone.parse()?;
+    let start = parse_local_datetime(start, &tz)?;
+    let end = parse_local_datetime(end, &tz)?;
+    if start >= end {
+        return Err(Error::UserInputError(format!(
+            "start {} must be before end {}",
+            start, end
+        )));
+    }
+    Ok(start <= now && now < end)
 }
 
 #[cfg(test)]
 mod timecheck_unit_test {
-    use chrono::{NaiveDate, TimeZone};
+    use chrono::{NaiveDate, TimeZone, Utc};
     use chrono_tz::Australia::Sydney;
-    use regex::Regex;
-    use std::str::FromStr;
+
+    use crate::time_check::{is_in_range, resolve_local, week_days, Margins, UpTimeWindow};
+
+    struct UpTimeCheck {
+        window: UpTimeWindow,
+        dt: chrono::DateTime<Utc>,
+    }
+
+    impl UpTimeCheck {
+        fn is_uptime(&self) -> Result<bool, crate::error::Error> {
+            Ok(self.window.is_uptime_at(self.dt, Margins::default()))
+        }
+    }
+
+    fn window(r: &str) -> UpTimeWindow {
+        r.parse().unwrap()
+    }
 
     struct CurrentDateTime {
         year: i32,
@@ -233,30 +308,36 @@ mod timecheck_unit_test {
             }
         }
         fn get_data(&self, r: &str) -> UpTimeCheck {
-            let v = Regex::new(
-                r"^([a-zA-Z]{3})-([a-zA-Z]{3}) (\d\d):(\d\d)-(\d\d):(\d\d) (?P<tz>[a-zA-Z/_]+)$",
-            )
-            .unwrap();
-            let m = v.captures(r).unwrap();
-
-            let nd = NaiveDate::from_ymd(self.year, self.month, self.day)
-                .and_hms(self.hour, self.min, self.sec);
+            let nd = NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+                .unwrap()
+                .and_hms_opt(self.hour, self.min, self.sec)
+                .unwrap();
             // ALl tests are in sydney timezone but can work with anyother
             let dt = Sydney.from_local_datetime(&nd).unwrap();
 
             UpTimeCheck {
-                week_start: current_day(&m[1]),
-                week_end: current_day(&m[2]),
-                low_hour: FromStr::from_str(&m[3]).unwrap(),
-                low_min: FromStr::from_str(&m[4]).unwrap(),
-                high_hour: FromStr::from_str(&m[5]).unwrap(),
-                high_min: FromStr::from_str(&m[6]).unwrap(),
-                dt,
+                window: window(r),
+                dt: dt.with_timezone(&Utc),
             }
         }
     }
 
-    use crate::time_check::{current_day, UpTimeCheck};
+    /// Returns true if the rule is up at the local datetime `YYYY-MM-DD HH:MM` in the rule's timezone
+    fn is_up_local(rule: &str, local: &str) -> bool {
+        let w = window(rule);
+        let naive = chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        w.is_uptime_at(
+            resolve_local(&w.tz, naive).with_timezone(&Utc),
+            Margins::default(),
+        )
+    }
+
+    /// Returns true if the rule is up at the UTC datetime `YYYY-MM-DD HH:MM`
+    fn is_up_utc(rule: &str, utc: &str) -> bool {
+        let naive = chrono::NaiveDateTime::parse_from_str(utc, "%Y-%m-%d %H:%M").unwrap();
+        window(rule).is_uptime_at(Utc.from_utc_datetime(&naive), Margins::default())
+    }
+
     #[test]
     // Rule    : Mon-Fri 00:00-23:59 Australia/Sydney
     // Uptime  : Mon(00:00 AM)-Fri(23:59PM)
@@ -265,36 +346,36 @@ mod timecheck_unit_test {
         let rule = "Mon-Fri 00:00-23:59 Australia/Sydney";
         // Datetime: 29-Aug-2022 Day: Monday Time:01 AM
         // Expected : Resources should be UP
-        let mut cdt = CurrentDateTime::new(2022, 08, 29, 01, 00, 00);
+        let mut cdt = CurrentDateTime::new(2022, 8, 29, 1, 0, 0);
         let mut u = cdt.get_data(rule);
         let z = u.is_uptime();
         print!("{z:?}");
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 30-Aug-2022 Day: Tuesday Time:03 AM
         // Expected : Resources should be UP
-        cdt = CurrentDateTime::new(2022, 08, 30, 03, 00, 00);
+        cdt = CurrentDateTime::new(2022, 8, 30, 3, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 02-Sep-2022 Day: Friday Time: 23:59 PM
         // Expected : Resources should be UP
-        cdt = CurrentDateTime::new(2022, 09, 02, 23, 59, 00);
+        cdt = CurrentDateTime::new(2022, 9, 2, 23, 59, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 03-Sep-2022 Day: Saturday Time: 00:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 03, 00, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 3, 0, 1, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 04-Sep-2022 Day: Sunday Time: 09:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 04, 09, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 4, 9, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 04-Sep-2022 Day: Sunday Time: 09:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 04, 09, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 4, 9, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
     }
 
     #[test]
@@ -309,49 +390,49 @@ mod timecheck_unit_test {
         let rule = "Mon-Fri 07:00-02:00 Australia/Sydney";
         // Datet: 05-Sep-2022 Day: Monday Time:07:01 AM
         // Expected : Resources should be UP
-        let mut cdt = CurrentDateTime::new(2022, 09, 05, 07, 01, 00);
+        let mut cdt = CurrentDateTime::new(2022, 9, 5, 7, 1, 0);
         let mut u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Date: 06-Sep-2022 Day: Tuesday Time:01:00 AM
         // Expected : Resources should be UP
-        cdt = CurrentDateTime::new(2022, 09, 06, 9, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 6, 9, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 09-Sep-2022 Day: Friday Time: 23:59 PM
         // Expected : Resources should be UP
-        cdt = CurrentDateTime::new(2022, 09, 09, 23, 59, 00);
+        cdt = CurrentDateTime::new(2022, 9, 9, 23, 59, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 10-Sep-2022 Day: Saturday Time: 01:59 AM
         // Expected : Resources should be UP
-        cdt = CurrentDateTime::new(2022, 09, 10, 01, 59, 00);
+        cdt = CurrentDateTime::new(2022, 9, 10, 1, 59, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Datetime: 10-Sep-2022 Day: Saturday Time: 02:01 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 10, 02, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 10, 2, 1, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 10-Sep-2022 Day: Saturday Time: 09:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 10, 09, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 10, 9, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 11-Sep-2022 Day: Sunday Time: 09:00 PM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 11, 21, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 11, 21, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 12-Sep-2022 Day: Monday Time: 03:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 12, 03, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 12, 3, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 12-Sep-2022 Day: Monday Time: 01:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 12, 01, 00, 00);
+        cdt = CurrentDateTime::new(2022, 9, 12, 1, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
     }
 
     #[test]
@@ -365,33 +446,328 @@ mod timecheck_unit_test {
         let rule = "Mon-Fri 07:00-19:00 Australia/Sydney";
         // Datet: 05-Sep-2022 Day: Monday Time:07:01 AM
         // Expected : Resources should be UP
-        let mut cdt = CurrentDateTime::new(2022, 09, 05, 07, 01, 00);
+        let mut cdt = CurrentDateTime::new(2022, 9, 5, 7, 1, 0);
         let mut u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), true);
+        assert!(u.is_uptime().unwrap());
         // Date: 05-Sep-2022 Day: Monday Time:07:01 PM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 05, 19, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 5, 19, 1, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Date: 06-Sep-2022 Day: Tuesday Time:05:00 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 06, 05, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 6, 5, 1, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 03-Sep-2022 Day: Friday Time: 07:59 PM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 03, 19, 59, 00);
+        cdt = CurrentDateTime::new(2022, 9, 3, 19, 59, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 10-Sep-2022 Day: Saturday Time: 07:01 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 10, 07, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 10, 7, 1, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
         // Datetime: 11-Sep-2022 Day: Sunday Time: 07:01 AM
         // Expected : Resources should be DOWN
-        cdt = CurrentDateTime::new(2022, 09, 11, 07, 01, 00);
+        cdt = CurrentDateTime::new(2022, 9, 11, 7, 1, 0);
+        u = cdt.get_data(rule);
+        assert!(!u.is_uptime().unwrap());
+    }
+
+    #[test]
+    // Rule    : Mon,Wed,Fri 07:00-19:00 Australia/Sydney
+    // Uptime  : Mon, Wed & Fri 7AM to 7PM
+    // Downtime: Tue, Thu, Sat & Sun
+    fn test_check_non_contiguous_week_days() {
+        let rule = "Mon,Wed,Fri 07:00-19:00 Australia/Sydney";
+        // Datetime: 05-Sep-2022 Day: Monday Time:08:00 AM
+        // Expected : Resources should be UP
+        let mut cdt = CurrentDateTime::new(2022, 9, 5, 8, 0, 0);
+        let mut u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+        // Datetime: 06-Sep-2022 Day: Tuesday Time:08:00 AM
+        // Expected : Resources should be DOWN
+        cdt = CurrentDateTime::new(2022, 9, 6, 8, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(!u.is_uptime().unwrap());
+        // Datetime: 07-Sep-2022 Day: Wednesday Time:08:00 AM
+        // Expected : Resources should be UP
+        cdt = CurrentDateTime::new(2022, 9, 7, 8, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+        // Datetime: 08-Sep-2022 Day: Thursday Time:08:00 AM
+        // Expected : Resources should be DOWN
+        cdt = CurrentDateTime::new(2022, 9, 8, 8, 0, 0);
         u = cdt.get_data(rule);
-        assert_eq!(u.is_uptime().unwrap(), false);
+        assert!(!u.is_uptime().unwrap());
+        // Datetime: 09-Sep-2022 Day: Friday Time:08:00 AM
+        // Expected : Resources should be UP
+        cdt = CurrentDateTime::new(2022, 9, 9, 8, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+    }
+
+    #[test]
+    // Rule    : Mon-Wed,Sat 07:00-02:00 Australia/Sydney
+    // Uptime  : Mon(7AM) to Thu(2AM) and Sat(7AM) to Sun(2AM)
+    fn test_check_mixed_week_days_extending_overnite() {
+        let rule = "Mon-Wed,Sat 07:00-02:00 Australia/Sydney";
+        // Datetime: 08-Sep-2022 Day: Thursday Time:01:00 AM
+        // Expected : Resources should be UP
+        let mut cdt = CurrentDateTime::new(2022, 9, 8, 1, 0, 0);
+        let mut u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+        // Datetime: 08-Sep-2022 Day: Thursday Time:08:00 AM
+        // Expected : Resources should be DOWN
+        cdt = CurrentDateTime::new(2022, 9, 8, 8, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(!u.is_uptime().unwrap());
+        // Datetime: 10-Sep-2022 Day: Saturday Time:01:00 AM
+        // Expected : Resources should be DOWN
+        cdt = CurrentDateTime::new(2022, 9, 10, 1, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(!u.is_uptime().unwrap());
+        // Datetime: 10-Sep-2022 Day: Saturday Time:11:00 PM
+        // Expected : Resources should be UP
+        cdt = CurrentDateTime::new(2022, 9, 10, 23, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+        // Datetime: 11-Sep-2022 Day: Sunday Time:01:00 AM
+        // Expected : Resources should be UP
+        cdt = CurrentDateTime::new(2022, 9, 11, 1, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(u.is_uptime().unwrap());
+        // Datetime: 11-Sep-2022 Day: Sunday Time:08:00 AM
+        // Expected : Resources should be DOWN
+        cdt = CurrentDateTime::new(2022, 9, 11, 8, 0, 0);
+        u = cdt.get_data(rule);
+        assert!(!u.is_uptime().unwrap());
+    }
+
+    #[test]
+    fn test_week_days() {
+        assert_eq!(week_days("Mon,Wed,Fri").unwrap(), vec![0, 2, 4]);
+        assert_eq!(week_days("Mon-Wed,Sat").unwrap(), vec![0, 1, 2, 5]);
+        assert_eq!(week_days("Mon-Fri").unwrap(), vec![0, 1, 2, 3, 4]);
+        // range wraps around the week
+        assert_eq!(week_days("Fri-Mon").unwrap(), vec![4, 5, 6, 0]);
+    }
+
+    #[test]
+    fn test_invalid_window() {
+        let err = |r: &str| r.parse::<UpTimeWindow>().unwrap_err().to_string();
+        assert_eq!(
+            err("Mon-Fry 07:00-19:00 Australia/Sydney"),
+            "Invalid User Input: uptime Mon-Fry 07:00-19:00 Australia/Sydney: Invalid day Fry, expected one of Mon, Tue, Wed, Thu, Fri, Sat, Sun"
+        );
+        assert_eq!(
+            err("Mon-Fri 07:00-24:00 Australia/Sydney"),
+            "Invalid User Input: uptime Mon-Fri 07:00-24:00 Australia/Sydney: Invalid time 24:00, expected 00:00 to 23:59"
+        );
+        assert_eq!(
+            err("Mon-Fri 07:00-19:00 India/Sydney"),
+            "Invalid User Input: uptime Mon-Fri 07:00-19:00 India/Sydney: Invalid timezone India/Sydney, expected an Olson timezone for example Australia/Sydney"
+        );
+    }
+
+    #[test]
+    fn test_in_range() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap(); // 10AM in Sydney
+        assert!(is_in_range(
+            "2024-06-01 00:00",
+            "2024-06-08 00:00",
+            "Australia/Sydney",
+            now
+        )
+        .unwrap());
+        assert!(is_in_range(
+            "2024-06-03 10:00",
+            "2024-06-03 11:00",
+            "Australia/Sydney",
+            now
+        )
+        .unwrap());
+        // end is exclusive
+        assert!(!is_in_range(
+            "2024-06-03 09:00",
+            "2024-06-03 10:00",
+            "Australia/Sydney",
+            now
+        )
+        .unwrap());
+        assert!(!is_in_range("2024-06-03 10:00", "2024-06-03 11:00", "UTC", now).unwrap());
+        assert!(is_in_range("2024-06-03 11:00", "2024-06-03 10:00", "UTC", now).is_err());
+        assert!(is_in_range("2024-06-03", "2024-06-04", "UTC", now).is_err());
+    }
+
+    #[test]
+    // Rule    : Mon-Sun 22:00-02:00 Europe/London
+    // Uptime  : every night from 10PM until 2AM the next day
+    fn test_window_spanning_midnight() {
+        let rule = "Mon-Sun 22:00-02:00 Europe/London";
+        assert!(is_up_local(rule, "2024-06-05 23:30"));
+        assert!(is_up_local(rule, "2024-06-06 00:30"));
+        assert!(is_up_local(rule, "2024-06-06 02:00"));
+        assert!(!is_up_local(rule, "2024-06-06 02:01"));
+        assert!(!is_up_local(rule, "2024-06-06 21:59"));
+    }
+
+    #[test]
+    // Windows opened on the last day of a month extending into the first day of the next month
+    fn test_window_spanning_month_end() {
+        // Wednesday 31-Jan-2024 to Thursday 01-Feb-2024
+        let rule = "Wed-Wed 20:00-04:00 Australia/Sydney";
+        assert!(is_up_local(rule, "2024-01-31 23:00"));
+        assert!(is_up_local(rule, "2024-02-01 03:00"));
+        assert!(!is_up_local(rule, "2024-02-01 05:00"));
+        // Thursday 29-Feb-2024 (leap day) to Friday 01-Mar-2024
+        let rule = "Thu-Thu 20:00-04:00 Australia/Sydney";
+        assert!(is_up_local(rule, "2024-02-29 21:00"));
+        assert!(is_up_local(rule, "2024-03-01 03:59"));
+        assert!(!is_up_local(rule, "2024-03-01 04:01"));
+        // Tuesday 30-Apr-2024 to Wednesday 01-May-2024
+        let rule = "Tue-Tue 20:00-04:00 Asia/Kolkata";
+        assert!(is_up_local(rule, "2024-05-01 01:00"));
+        // Wednesday 01-May-2024 did not open a window
+        assert!(!is_up_local(rule, "2024-05-01 21:00"));
+    }
+
+    #[test]
+    // Tuesday 31-Dec-2024 to Wednesday 01-Jan-2025
+    fn test_window_spanning_year_end() {
+        let rule = "Tue-Tue 20:00-04:00 Australia/Sydney";
+        assert!(is_up_local(rule, "2024-12-31 20:01"));
+        assert!(is_up_local(rule, "2025-01-01 00:00"));
+        assert!(is_up_local(rule, "2025-01-01 03:59"));
+        assert!(!is_up_local(rule, "2025-01-01 04:30"));
+        assert!(!is_up_local(rule, "2024-12-31 19:59"));
+    }
+
+    #[test]
+    // Sydney moves from 02:00 AEST to 03:00 AEDT on Sunday 06-Oct-2024
+    fn test_window_start_in_dst_gap() {
+        let rule = "Sun-Sun 02:30-10:00 Australia/Sydney";
+        // 02:30 doesn't exist on the day, the window opens at 03:30 AEDT i.e. 16:30 UTC
+        assert!(!is_up_utc(rule, "2024-10-05 16:15"));
+        assert!(is_up_utc(rule, "2024-10-05 16:45"));
+        // 10:00 AEDT is 23:00 UTC
+        assert!(is_up_utc(rule, "2024-10-05 23:00"));
+        assert!(!is_up_utc(rule, "2024-10-05 23:01"));
+        // New York moves from 02:00 EST to 03:00 EDT on Sunday 10-Mar-2024,
+        // the window end 02:30 doesn't exist and closes at 03:30 EDT i.e. 07:30 UTC
+        let rule = "Sun-Sun 01:30-02:30 America/New_York";
+        assert!(!is_up_utc(rule, "2024-03-10 06:15"));
+        assert!(is_up_utc(rule, "2024-03-10 07:15"));
+        assert!(!is_up_utc(rule, "2024-03-10 07:45"));
+    }
+
+    #[test]
+    // Sydney moves from 03:00 AEDT back to 02:00 AEST on Sunday 07-Apr-2024,
+    // the local time 02:30 occurs twice and the window opens on the first
+    fn test_window_start_in_dst_overlap() {
+        let rule = "Sun-Sun 02:30-10:00 Australia/Sydney";
+        // 02:15 AEDT
+        assert!(!is_up_utc(rule, "2024-04-06 15:15"));
+        // 02:45 AEDT
+        assert!(is_up_utc(rule, "2024-04-06 15:45"));
+        // 02:15 AEST, the second 02:15 of the day
+        assert!(is_up_utc(rule, "2024-04-06 16:15"));
+        // 10:00 AEST is 00:00 UTC
+        assert!(is_up_utc(rule, "2024-04-07 00:00"));
+        assert!(!is_up_utc(rule, "2024-04-07 00:01"));
+    }
+
+    #[test]
+    // Windows are evaluated in local time irrespective of the UTC offset in effect
+    fn test_window_follows_utc_offset() {
+        let rule = "Mon-Fri 09:00-17:00 Europe/London";
+        // Monday 01-Apr-2024 is BST(+01:00), 09:30 local time is 08:30 UTC
+        assert!(is_up_utc(rule, "2024-04-01 08:30"));
+        assert!(!is_up_utc(rule, "2024-04-01 07:30"));
+        assert!(!is_up_utc(rule, "2024-04-01 16:30"));
+        // Monday 08-Jan-2024 is GMT(+00:00)
+        assert!(is_up_utc(rule, "2024-01-08 16:30"));
+        assert!(!is_up_utc(rule, "2024-01-08 08:30"));
+        // Asia/Kolkata doesn't observe DST and is always +05:30
+        let rule = "Mon-Fri 09:00-17:00 Asia/Kolkata";
+        assert!(is_up_utc(rule, "2024-04-01 03:31"));
+        assert!(!is_up_utc(rule, "2024-04-01 03:29"));
+        assert!(is_up_utc(rule, "2024-10-07 11:30"));
+        assert!(!is_up_utc(rule, "2024-10-07 11:31"));
+    }
+
+    #[test]
+    // Window start and end are same hence no uptime
+    fn test_window_without_duration() {
+        assert!(!is_up_local(
+            "Mon-Sun 09:00-09:00 Australia/Sydney",
+            "2024-04-01 09:00"
+        ));
+    }
+
+    #[test]
+    fn test_in_range_dst_gap() {
+        // 02:30 on 06-Oct-2024 doesn't exist in Sydney and is 03:30 AEDT(16:30 UTC)
+        let now = Utc.with_ymd_and_hms(2024, 10, 5, 16, 15, 0).unwrap();
+        assert!(!is_in_range(
+            "2024-10-06 02:30",
+            "2024-10-06 12:00",
+            "Australia/Sydney",
+            now
+        )
+        .unwrap());
+        let now = Utc.with_ymd_and_hms(2024, 10, 5, 16, 30, 0).unwrap();
+        assert!(is_in_range(
+            "2024-10-06 02:30",
+            "2024-10-06 12:00",
+            "Australia/Sydney",
+            now
+        )
+        .unwrap());
+    }
+
+    #[test]
+    // Rule    : Mon-Fri 07:00-19:00 Australia/Sydney with prewarm 15m and grace 1h30m
+    // Uptime  : Mon(06:45 AM)-Mon(20:30 PM) ... Fri(06:45 AM)-Fri(20:30 PM)
+    fn test_window_with_prewarm_and_grace() {
+        let w = window("Mon-Fri 07:00-19:00 Australia/Sydney");
+        let margins = Margins::parse(Some("15m"), Some("1h30m")).unwrap();
+        let at = |local: &str| {
+            let naive = chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+            w.is_uptime_at(resolve_local(&w.tz, naive).with_timezone(&Utc), margins)
+        };
+        // Monday 02-Sep-2024
+        assert!(!at("2024-09-02 06:40"));
+        assert!(at("2024-09-02 06:50"));
+        assert!(at("2024-09-02 20:30"));
+        assert!(!at("2024-09-02 20:31"));
+        // Saturday 07-Sep-2024 01:00 AM, Friday's grace is over
+        assert!(!at("2024-09-07 01:00"));
+        // Sunday 08-Sep-2024 23:59 PM, Monday's prewarm hasn't started
+        assert!(!at("2024-09-08 23:59"));
+    }
+
+    #[test]
+    // Prewarm longer than a day starts the upscale on a previous day
+    fn test_window_with_prewarm_over_a_day() {
+        let w = window("Mon-Mon 09:00-17:00 Europe/London");
+        let margins = Margins::parse(Some("2days"), None).unwrap();
+        // Saturday 06-Jan-2024 09:30 AM, 47.5 hours before Monday 09:00 AM
+        let now = Utc.with_ymd_and_hms(2024, 1, 6, 9, 30, 0).unwrap();
+        assert!(w.is_uptime_at(now, margins));
+        // Saturday 06-Jan-2024 08:30 AM
+        let now = Utc.with_ymd_and_hms(2024, 1, 6, 8, 30, 0).unwrap();
+        assert!(!w.is_uptime_at(now, margins));
+    }
+
+    #[test]
+    fn test_invalid_margins() {
+        assert!(Margins::parse(Some("15x"), None)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid User Input: Invalid duration 15x"));
+        assert_eq!(Margins::parse(None, None).unwrap(), Margins::default());
     }
 }
@@ -1,11 +1,14 @@
 use core::time;
-use k8s_openapi::api::autoscaling::v1::HorizontalPodAutoscaler;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use k8s_openapi::api::{
     apps::v1::{Deployment, StatefulSet},
     batch::v1::CronJob,
 };
+use kube::api::PostParams;
 use kube::{Api, Client};
 use lazy_static::lazy_static;
+use saver::clock::SystemClock;
+use saver::downscaler::resource::argocd::{Application, ORIGINAL_SYNC_POLICY_ANNOTATION};
 use saver::downscaler::Rules;
 use saver::ScaleState;
 use std::fs::File;
@@ -25,9 +28,16 @@ async fn test1_namespace() {
     // sleep for 10 sec so that hpa will scale the replicas original count =3 since hpa
     tokio::time::sleep(time::Duration::from_millis(10000)).await;
 
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // test if all Deployment are downscaled in namespace
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber1");
     let d = api.get("test-kuber1-deploy1").await.unwrap();
@@ -117,7 +127,7 @@ async fn test1_namespace() {
     // test if all Jobs are disabled in namespace
     let api: Api<CronJob> = Api::namespaced(client.clone(), "kuber1");
     let d = api.get("test-kuber1-cj1").await.unwrap();
-    assert_eq!(d.spec.unwrap().suspend.unwrap(), true);
+    assert!(d.spec.unwrap().suspend.unwrap());
     assert_eq!(
         d.metadata
             .annotations
@@ -130,7 +140,7 @@ async fn test1_namespace() {
     // test if all Jobs are NOT disabled in namespace
     let api: Api<CronJob> = Api::namespaced(client.clone(), "kuber2");
     let d = api.get("test-kuber2-cj2").await.unwrap();
-    assert_eq!(d.spec.unwrap().suspend.unwrap(), false);
+    assert!(!d.spec.unwrap().suspend.unwrap());
     assert_eq!(
         d.metadata
             .annotations
@@ -148,9 +158,16 @@ async fn test2_deployment() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber3");
     let d = api.get("test-kuber3-deploy1").await.unwrap();
@@ -167,9 +184,16 @@ async fn test2_statefulset() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let api: Api<StatefulSet> = Api::namespaced(client.clone(), "kuber3");
     let d = api.get("test-kuber3-ss1").await.unwrap();
@@ -186,13 +210,20 @@ async fn test3_cronjob() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must suspend the cronjob
     let api: Api<CronJob> = Api::namespaced(client.clone(), "kuber9");
     let d = api.get("test-kuber9-cj1").await.unwrap();
-    assert_eq!(d.spec.unwrap().suspend.unwrap(), true);
+    assert!(d.spec.unwrap().suspend.unwrap());
     assert_eq!(
         d.metadata
             .annotations
@@ -204,7 +235,7 @@ async fn test3_cronjob() {
     );
     //  // kube-saver must NOT suspend the cronjob
     let d = api.get("test-kuber9-cj2").await.unwrap();
-    assert_eq!(d.spec.unwrap().suspend.unwrap(), false);
+    assert!(!d.spec.unwrap().suspend.unwrap());
     assert_eq!(
         d.metadata
             .annotations
@@ -222,9 +253,16 @@ async fn test2_scaledown_scaledupresource() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber7");
     let d = api.get("test-kuber7-deploy1").await.unwrap();
@@ -243,9 +281,16 @@ async fn test2_scaleup_scaleddownresource() {
 
     //initially should be zero
     assert_eq!(d.spec.unwrap().replicas, Some(0));
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let d = api.get("test-kuber8-deploy1").await.unwrap();
     assert_eq!(d.spec.unwrap().replicas, Some(2));
@@ -258,9 +303,16 @@ async fn test4_hpa() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must set minReplicas =1 in the hpa
     let api: Api<HorizontalPodAutoscaler> = Api::namespaced(client.clone(), "kuber12c");
     let d = api.get("test-kuber12c-hpa1").await.unwrap();
@@ -301,9 +353,16 @@ async fn test4_hpa_scale_all_resources_replicas_1() {
     let d = api.get("test-kuber12-deploy1").await.unwrap();
     assert_eq!(d.spec.unwrap().replicas, Some(3));
 
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must set minReplicas =1 in the cronjob
     let hpa_api: Api<HorizontalPodAutoscaler> = Api::namespaced(client.clone(), "kuber12");
     let hpa = hpa_api.get("test-kuber12-hpa").await.unwrap();
@@ -326,9 +385,16 @@ async fn test4_hpa_scale_all_resources_replicas_1() {
     // now test if they are getting scaled up to orignal replicas
     let f = File::open("tests/rules/rules12a.yaml").unwrap();
     let r: Rules = serde_yaml::from_reader(f).unwrap();
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     let hpa_api: Api<HorizontalPodAutoscaler> = Api::namespaced(client.clone(), "kuber12");
     let hpa = hpa_api.get("test-kuber12-hpa").await.unwrap();
     //back to original replicas
@@ -356,11 +422,69 @@ async fn test5_check_if_ignored() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must ignore
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber14");
     let d = api.get("test-kuber14-deploy1").await.unwrap();
     assert_eq!(d.spec.unwrap().replicas, Some(2));
 }
+
+#[tokio::test]
+async fn test15_namespace_pause_argocd() {
+    let f = File::open("tests/rules/rules15.yaml").unwrap();
+    let r: Rules = serde_yaml::from_reader(f).unwrap();
+    let client = Client::try_default()
+        .await
+        .expect("Failed to read kubeconfig");
+    // the application managing the deployment of the namespace, with self-heal
+    let apps: Api<Application> = Api::namespaced(client.clone(), "kuber15");
+    let app: Application = serde_json::from_value(serde_json::json!({
+        "apiVersion": "argoproj.io/v1alpha1",
+        "kind": "Application",
+        "metadata": { "name": "app15", "namespace": "kuber15" },
+        "spec": { "project": "default", "syncPolicy": { "automated": { "selfHeal": true } } }
+    }))
+    .unwrap();
+    apps.create(&PostParams::default(), &app).await.ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
+    let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber15");
+    let d = api.get("test-kuber15-deploy1").await.unwrap();
+    assert_eq!(d.spec.unwrap().replicas, Some(0));
+    // the automated sync is paused for the downtime and saved in the annotations
+    let app = apps.get("app15").await.unwrap();
+    assert_eq!(
+        app.spec
+            .sync_policy
+            .as_ref()
+            .and_then(|p| p.get("automated"))
+            .filter(|a| !a.is_null()),
+        None
+    );
+    assert_eq!(
+        app.metadata
+            .annotations
+            .as_ref()
+            .unwrap()
+            .get(ORIGINAL_SYNC_POLICY_ANNOTATION)
+            .unwrap(),
+        "{\"selfHeal\":true}"
+    );
+}
@@ -1,15 +1,14 @@
-use k8s_openapi::api::autoscaling::v1::HorizontalPodAutoscaler;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler;
 use k8s_openapi::api::{
     apps::v1::{Deployment, StatefulSet},
     batch::v1::CronJob,
 };
+use saver::clock::SystemClock;
 use saver::controller::upscaler::{
     enable_cronjob, upscale_deploy, upscale_hpa, upscale_ns, upscale_statefulset,
 };
-use saver::downscaler::Rules;
-use saver::ScaleState;
+use saver::downscaler::{Rules, Selection};
 use std::fs::File;
-use std::sync::Arc;
 
 use kube::{api::Api, Client};
 
@@ -22,9 +21,16 @@ async fn test4_apply_upscaler_on_downscaled_for_deployment() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber4");
     let d = api.get("test-kuber4-deploy1").await.unwrap();
@@ -33,7 +39,13 @@ async fn test4_apply_upscaler_on_downscaled_for_deployment() {
     assert_eq!(d.spec.unwrap().replicas, Some(0));
     let exp = "metadata.name=='test-kuber4-deploy1'";
 
-    upscale_deploy(client.clone(), None, exp).await.ok();
+    upscale_deploy(
+        client.clone(),
+        None,
+        &Selection::jmespath("upscaler", exp).unwrap(),
+    )
+    .await
+    .ok();
     // kubectl apply upscaler.yaml
     // // Upsale CR must scale up test-kuber4-deploy1 to 2
     let d = api.get("test-kuber4-deploy1").await.unwrap();
@@ -49,9 +61,16 @@ async fn test5_apply_upscaler_on_downscaled_for_namespace() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     // kube-saver must scale down to 0
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber5");
     let d = api.get("test-kuber5-deploy1").await.unwrap();
@@ -65,10 +84,16 @@ async fn test5_apply_upscaler_on_downscaled_for_namespace() {
 
     let c: Api<CronJob> = Api::namespaced(client.clone(), "kuber5");
     let c_api = c.get("test-kuber5-cj1").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), true);
+    assert!(c_api.spec.unwrap().suspend.unwrap());
 
     let exp = "metadata.name=='kuber5'";
-    upscale_ns(client.clone(), None, exp).await.ok();
+    upscale_ns(
+        client.clone(),
+        None,
+        &Selection::jmespath("upscaler", exp).unwrap(),
+    )
+    .await
+    .ok();
     // kubectl apply upscaler.yaml
     // // Upsale CR must scale up test-kuber4-deploy1 to 2
     let api: Api<Deployment> = Api::namespaced(client.clone(), "kuber5");
@@ -81,7 +106,7 @@ async fn test5_apply_upscaler_on_downscaled_for_namespace() {
     assert_eq!(s_api.spec.unwrap().replicas, Some(1));
     let c: Api<CronJob> = Api::namespaced(client.clone(), "kuber5");
     let c_api = c.get("test-kuber5-cj1").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), false);
+    assert!(!c_api.spec.unwrap().suspend.unwrap());
 }
 
 #[tokio::test]
@@ -91,14 +116,27 @@ async fn test5_apply_upscaler_on_downscaled_for_statefulset() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     let api: Api<StatefulSet> = Api::namespaced(client.clone(), "kuber6");
     let d = api.get("test-kuber6-ss2").await.unwrap();
     assert_eq!(d.spec.unwrap().replicas, Some(0));
     let exp = "metadata.name=='test-kuber6-ss2'";
-    upscale_statefulset(client.clone(), None, exp).await.ok();
+    upscale_statefulset(
+        client.clone(),
+        None,
+        &Selection::jmespath("upscaler", exp).unwrap(),
+    )
+    .await
+    .ok();
     let api: Api<StatefulSet> = Api::namespaced(client.clone(), "kuber6");
     let d = api.get("test-kuber6-ss2").await.unwrap();
     assert_eq!(d.spec.unwrap().replicas, Some(1));
@@ -111,21 +149,33 @@ async fn test5_apply_upscaler_on_downscaled_for_cj() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     let api: Api<CronJob> = Api::namespaced(client.clone(), "kuber10");
     let c_api = api.get("test-kuber10-cj1").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), true);
+    assert!(c_api.spec.unwrap().suspend.unwrap());
     let c_api = api.get("test-kuber10-cj2").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), true);
+    assert!(c_api.spec.unwrap().suspend.unwrap());
     let exp = "metadata.name=='test-kuber10-cj1' || metadata.name=='test-kuber10-cj2'";
-    enable_cronjob(client.clone(), exp).await.ok();
+    enable_cronjob(
+        client.clone(),
+        &Selection::jmespath("upscaler", exp).unwrap(),
+    )
+    .await
+    .ok();
     let api: Api<CronJob> = Api::namespaced(client.clone(), "kuber10");
     let c_api = api.get("test-kuber10-cj1").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), false);
+    assert!(!c_api.spec.unwrap().suspend.unwrap());
     let c_api = api.get("test-kuber10-cj2").await.unwrap();
-    assert_eq!(c_api.spec.unwrap().suspend.unwrap(), false);
+    assert!(!c_api.spec.unwrap().suspend.unwrap());
 }
 
 #[tokio::test]
@@ -135,16 +185,29 @@ async fn test5_apply_upscaler_on_downscaled_for_hpa() {
     let client = Client::try_default()
         .await
         .expect("Failed to read kubeconfig");
-    r.process_rules(client.clone(), None, None, SCALED_STATE.clone())
-        .await
-        .ok();
+    r.process_rules(
+        client.clone(),
+        None,
+        None,
+        SCALED_STATE.clone(),
+        &SystemClock,
+        false,
+    )
+    .await
+    .ok();
     let api: Api<HorizontalPodAutoscaler> = Api::namespaced(client.clone(), "kuber12b");
     let hpa_api = api.get("test-kuber12b-hpa1").await.unwrap();
     assert_eq!(hpa_api.spec.unwrap().min_replicas, Some(1));
     let hpa_api = api.get("test-kuber12b-hpa2").await.unwrap();
     assert_eq!(hpa_api.spec.unwrap().min_replicas, Some(1));
     let exp = "metadata.name=='test-kuber12b-hpa1' || metadata.name=='test-kuber12b-hpa2'";
-    upscale_hpa(client.clone(), None, exp).await.ok();
+    upscale_hpa(
+        client.clone(),
+        None,
+        &Selection::jmespath("upscaler", exp).unwrap(),
+    )
+    .await
+    .ok();
     let api: Api<HorizontalPodAutoscaler> = Api::namespaced(client.clone(), "kuber12b");
     let h_api = api.get("test-kuber12b-hpa1").await.unwrap();
     assert_eq!(h_api.spec.unwrap().min_replicas, Some(3));
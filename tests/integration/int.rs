@@ -23,6 +23,8 @@ async fn test6_check_if_upscales() -> Result<()> {
         None,
         None,
         std::sync::Arc::new(saver::ScaleState::new()),
+        &saver::clock::SystemClock,
+        false,
     )
     .await
     .ok();
@@ -33,7 +35,7 @@ async fn test6_check_if_upscales() -> Result<()> {
     // All the below resources must be scaled down to zero
 
     let cj_api = cj.get("test-kuber13-cj1").await.unwrap();
-    assert_eq!(cj_api.spec.unwrap().suspend.unwrap(), true);
+    assert!(cj_api.spec.unwrap().suspend.unwrap());
 
     let deploy_api = deploy.get("test-kuber13-deploy1").await.unwrap();
     assert_eq!(deploy_api.spec.unwrap().replicas, Some(0));
@@ -43,7 +45,7 @@ async fn test6_check_if_upscales() -> Result<()> {
     // All the below resources must not be scaled down
 
     let cj_api = cj.get("test-kuber13-cj2").await.unwrap();
-    assert_eq!(cj_api.spec.unwrap().suspend.unwrap(), false);
+    assert!(!cj_api.spec.unwrap().suspend.unwrap());
 
     let deploy_api = deploy.get("test-kuber13-deploy2").await.unwrap();
     assert_eq!(deploy_api.spec.unwrap().replicas, Some(2));
@@ -61,7 +63,7 @@ async fn test6_check_if_upscales() -> Result<()> {
     // lets test it with the actual controller in the
 
     let cj_api = cj.get("test-kuber13-cj1").await.unwrap();
-    assert_eq!(cj_api.spec.unwrap().suspend.unwrap(), false);
+    assert!(!cj_api.spec.unwrap().suspend.unwrap());
 
     let deploy_api = deploy.get("test-kuber13-deploy1").await.unwrap();
     assert_eq!(deploy_api.spec.unwrap().replicas, Some(2));